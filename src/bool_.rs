@@ -0,0 +1,78 @@
+use crate::UntaggedEnumVisitor;
+use core::ops::BitOr;
+
+/// Which non-boolean inputs to deserialize using the `bool` closure, for use
+/// with [`UntaggedEnumVisitor::bool_coercion`].
+///
+/// Strings are matched case-insensitively. Combine cases using `|`.
+///
+/// [`UntaggedEnumVisitor::bool_coercion`]: crate::UntaggedEnumVisitor::bool_coercion
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BoolCoercion {
+    bits: u8,
+}
+
+impl BoolCoercion {
+    /// Only actual booleans.
+    pub const NONE: Self = BoolCoercion { bits: 0 };
+    /// The strings `"true"` and `"false"`.
+    pub const TRUE_FALSE: Self = BoolCoercion { bits: 1 << 0 };
+    /// The strings `"yes"` and `"no"`.
+    pub const YES_NO: Self = BoolCoercion { bits: 1 << 1 };
+    /// The strings `"on"` and `"off"`.
+    pub const ON_OFF: Self = BoolCoercion { bits: 1 << 2 };
+    /// The strings `"1"` and `"0"`.
+    pub const ONE_ZERO: Self = BoolCoercion { bits: 1 << 3 };
+    /// The integers 1 and 0, if no integer closure accepts them.
+    pub const INTEGER: Self = BoolCoercion { bits: 1 << 4 };
+
+    pub const fn contains(self, other: Self) -> bool {
+        self.bits & other.bits == other.bits
+    }
+}
+
+impl BitOr for BoolCoercion {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        BoolCoercion {
+            bits: self.bits | other.bits,
+        }
+    }
+}
+
+impl<'closure, 'de, Value> UntaggedEnumVisitor<'closure, 'de, Value> {
+    pub(crate) fn parse_bool_str(&self, v: &str) -> Option<bool> {
+        const STRINGS: [(BoolCoercion, &str, &str); 4] = [
+            (BoolCoercion::TRUE_FALSE, "true", "false"),
+            (BoolCoercion::YES_NO, "yes", "no"),
+            (BoolCoercion::ON_OFF, "on", "off"),
+            (BoolCoercion::ONE_ZERO, "1", "0"),
+        ];
+
+        if self.visit_bool.is_none() || self.bool_coercion == BoolCoercion::NONE {
+            return None;
+        }
+        for (coercion, true_str, false_str) in STRINGS {
+            if self.bool_coercion.contains(coercion) {
+                if v.eq_ignore_ascii_case(true_str) {
+                    return Some(true);
+                } else if v.eq_ignore_ascii_case(false_str) {
+                    return Some(false);
+                }
+            }
+        }
+        None
+    }
+
+    pub(crate) fn coerce_int_to_bool(&self, v: Option<u8>) -> Option<bool> {
+        if self.visit_bool.is_none() || !self.bool_coercion.contains(BoolCoercion::INTEGER) {
+            return None;
+        }
+        match v {
+            Some(0) => Some(false),
+            Some(1) => Some(true),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use crate::coercion::Coercions;
+use crate::UntaggedEnumVisitor;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::BitOr;
+use core::str;
+use serde::de::{Expected, SeqAccess, Unexpected, Visitor};
+
+/// Which non-byte inputs to deserialize using the `bytes`, `borrowed_bytes`
+/// or `byte_buf` closure, for use with
+/// [`UntaggedEnumVisitor::bytes_coercion`].
+///
+/// Combine cases using `|`. If a string is accepted by more than one case,
+/// hex takes precedence over base64, which takes precedence over UTF-8.
+///
+/// [`UntaggedEnumVisitor::bytes_coercion`]: crate::UntaggedEnumVisitor::bytes_coercion
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BytesCoercion {
+    bits: u8,
+}
+
+impl BytesCoercion {
+    /// Only actual byte arrays.
+    pub const NONE: Self = BytesCoercion { bits: 0 };
+    /// The UTF-8 encoding of a string, borrowed from the input if the string
+    /// is.
+    pub const STR: Self = BytesCoercion { bits: 1 << 0 };
+    /// A sequence of integers between 0 and 255.
+    pub const SEQ: Self = BytesCoercion { bits: 1 << 1 };
+    /// A string of hexadecimal digit pairs, like `"c0ffee"`.
+    pub const HEX: Self = BytesCoercion { bits: 1 << 2 };
+    /// A base64 string, in either the standard or URL-safe alphabet, with or
+    /// without padding.
+    pub const BASE64: Self = BytesCoercion { bits: 1 << 3 };
+
+    pub const fn contains(self, other: Self) -> bool {
+        self.bits & other.bits == other.bits
+    }
+}
+
+impl BitOr for BytesCoercion {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        BytesCoercion {
+            bits: self.bits | other.bits,
+        }
+    }
+}
+
+pub(crate) enum Bytes<'a, 'de> {
+    Borrowed(&'de [u8]),
+    Transient(&'a [u8]),
+    Owned(Vec<u8>),
+}
+
+impl<'closure, 'de, Value> UntaggedEnumVisitor<'closure, 'de, Value> {
+    pub(crate) fn has_bytes_handler(&self) -> bool {
+        self.visit_bytes.is_some()
+            || self.visit_borrowed_bytes.is_some()
+            || self.visit_byte_buf.is_some()
+    }
+
+    // Decode a string into bytes according to the configured coercions, or
+    // return None if none of them apply to strings.
+    pub(crate) fn str_to_bytes<'a, E>(&self, v: &'a str) -> Option<Result<Bytes<'a, 'de>, E>>
+    where
+        E: serde::de::Error,
+    {
+        if !self.has_bytes_handler() {
+            return None;
+        }
+        let hex = self.bytes_coercion.contains(BytesCoercion::HEX);
+        let base64 = self.bytes_coercion.contains(BytesCoercion::BASE64);
+        if hex {
+            if let Some(bytes) = decode_hex(v) {
+                return Some(Ok(Bytes::Owned(bytes)));
+            }
+        }
+        if base64 {
+            if let Some(bytes) = decode_base64(v) {
+                return Some(Ok(Bytes::Owned(bytes)));
+            }
+        }
+        if self.bytes_coercion.contains(BytesCoercion::STR) {
+            return Some(Ok(Bytes::Transient(v.as_bytes())));
+        }
+        let expected = match (hex, base64) {
+            (true, true) => "a hex or base64 string",
+            (true, false) => "a hex string",
+            (false, true) => "a base64 string",
+            (false, false) => return None,
+        };
+        Some(Err(E::invalid_value(Unexpected::Str(v), &expected)))
+    }
+
+    // Validate bytes as UTF-8 if they are to be passed to a string closure
+    // instead of a bytes closure.
+    pub(crate) fn bytes_to_str<'a, E>(&self, v: &'a [u8]) -> Option<Result<&'a str, E>>
+    where
+        E: serde::de::Error,
+    {
+        if !self.coercions.contains(Coercions::BYTES_AS_STR)
+            || self.has_bytes_handler()
+            || self.visit_str.is_none() && self.visit_borrowed_str.is_none()
+        {
+            return None;
+        }
+        Some(str::from_utf8(v).map_err(|err| {
+            let expected = Utf8 {
+                offset: err.valid_up_to(),
+            };
+            E::invalid_value(Unexpected::Bytes(v), &expected)
+        }))
+    }
+
+    // Pass the bytes to whichever bytes closure can take them with the least
+    // copying.
+    pub(crate) fn visit_coerced_bytes<E>(self, bytes: Bytes<'_, 'de>) -> Result<Value, E>
+    where
+        E: serde::de::Error,
+    {
+        match bytes {
+            Bytes::Borrowed(v) => {
+                if self.visit_borrowed_bytes.is_none() && self.visit_bytes.is_none() {
+                    self.visit_byte_buf(v.to_vec())
+                } else {
+                    self.visit_borrowed_bytes(v)
+                }
+            }
+            Bytes::Transient(v) => {
+                if self.visit_bytes.is_none() && self.visit_byte_buf.is_some() {
+                    self.visit_byte_buf(v.to_vec())
+                } else {
+                    self.visit_bytes(v)
+                }
+            }
+            Bytes::Owned(v) => self.visit_byte_buf(v),
+        }
+    }
+}
+
+// "UTF-8 text, which is invalid starting at byte 3"
+struct Utf8 {
+    offset: usize,
+}
+
+impl Expected for Utf8 {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "UTF-8 text, which is invalid starting at byte {}",
+            self.offset,
+        )
+    }
+}
+
+pub(crate) fn seq_to_bytes<'de, A>(mut seq: A) -> Result<Vec<u8>, A::Error>
+where
+    A: SeqAccess<'de>,
+{
+    let mut bytes = Vec::new();
+    while let Some(byte) = seq.next_element()? {
+        bytes.push(byte);
+    }
+    Ok(bytes)
+}
+
+fn decode_hex(string: &str) -> Option<Vec<u8>> {
+    fn digit(b: u8) -> Option<u8> {
+        match b {
+            b'0'..=b'9' => Some(b - b'0'),
+            b'a'..=b'f' => Some(b - b'a' + 10),
+            b'A'..=b'F' => Some(b - b'A' + 10),
+            _ => None,
+        }
+    }
+
+    let string = string.as_bytes();
+    if string.len() % 2 != 0 {
+        return None;
+    }
+    let mut bytes = Vec::with_capacity(string.len() / 2);
+    for pair in string.chunks_exact(2) {
+        bytes.push((digit(pair[0])? << 4) | digit(pair[1])?);
+    }
+    Some(bytes)
+}
+
+fn decode_base64(string: &str) -> Option<Vec<u8>> {
+    fn sextet(b: u8) -> Option<u32> {
+        match b {
+            b'A'..=b'Z' => Some(u32::from(b - b'A')),
+            b'a'..=b'z' => Some(u32::from(b - b'a') + 26),
+            b'0'..=b'9' => Some(u32::from(b - b'0') + 52),
+            b'+' | b'-' => Some(62),
+            b'/' | b'_' => Some(63),
+            _ => None,
+        }
+    }
+
+    let mut string = string.as_bytes();
+    if string.len() % 4 == 0 {
+        if let Some(unpadded) = string.strip_suffix(b"==") {
+            string = unpadded;
+        } else if let Some(unpadded) = string.strip_suffix(b"=") {
+            string = unpadded;
+        }
+    }
+    if string.len() % 4 == 1 {
+        return None;
+    }
+    let mut bytes = Vec::with_capacity(string.len() * 3 / 4);
+    for chunk in string.chunks(4) {
+        let mut bits = 0;
+        for &b in chunk {
+            bits = (bits << 6) | sextet(b)?;
+        }
+        bits <<= 6 * (4 - chunk.len());
+        let [_, b0, b1, b2] = bits.to_be_bytes();
+        bytes.extend_from_slice(&[b0, b1, b2][..chunk.len() - 1]);
+    }
+    Some(bytes)
+}
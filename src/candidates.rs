@@ -0,0 +1,70 @@
+use crate::content::Content;
+use crate::error::Error;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt::{self, Display};
+use serde::de::Deserialize;
+
+/// Ordered list of types to attempt deserializing a buffered map or sequence
+/// as, for use with [`UntaggedEnumVisitor::map_candidates`] and
+/// [`UntaggedEnumVisitor::seq_candidates`].
+///
+/// [`UntaggedEnumVisitor::map_candidates`]: crate::UntaggedEnumVisitor::map_candidates
+/// [`UntaggedEnumVisitor::seq_candidates`]: crate::UntaggedEnumVisitor::seq_candidates
+pub struct Candidates<'closure, 'de, Value> {
+    candidates: Vec<Box<dyn FnOnce(Content<'de>) -> Result<Value, Error> + 'closure>>,
+}
+
+impl<'closure, 'de, Value> Candidates<'closure, 'de, Value> {
+    pub fn new() -> Self {
+        Candidates {
+            candidates: Vec::new(),
+        }
+    }
+
+    /// Attempt to deserialize the input as `T`, and if successful, build the
+    /// untagged enum from it using the given closure.
+    #[must_use]
+    pub fn try_as<T>(mut self, construct: impl FnOnce(T) -> Value + 'closure) -> Self
+    where
+        T: Deserialize<'de>,
+    {
+        self.candidates
+            .push(Box::new(|content| T::deserialize(content).map(construct)));
+        self
+    }
+
+    pub(crate) fn deserialize(mut self, content: Content<'de>) -> Result<Value, Error> {
+        let Some(last) = self.candidates.pop() else {
+            return Err(serde::de::Error::custom("no candidate types to try"));
+        };
+        let mut errors = Vec::new();
+        for candidate in self.candidates {
+            match candidate(content.clone()) {
+                Ok(value) => return Ok(value),
+                Err(error) => errors.push(error),
+            }
+        }
+        match last(content) {
+            Ok(value) => Ok(value),
+            Err(error) if errors.is_empty() => Err(error),
+            Err(error) => {
+                errors.push(error);
+                Err(serde::de::Error::custom(NoMatch(&errors)))
+            }
+        }
+    }
+}
+
+struct NoMatch<'a>(&'a [Error]);
+
+impl<'a> Display for NoMatch<'a> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("data did not match any candidate")?;
+        for (i, error) in self.0.iter().enumerate() {
+            let separator = if i == 0 { " (" } else { "; " };
+            write!(formatter, "{separator}{error}")?;
+        }
+        formatter.write_str(")")
+    }
+}
@@ -0,0 +1,30 @@
+// The coercions which are turned on by a builder method that takes no
+// argument, such as `UntaggedEnumVisitor::float_to_int`.
+#[derive(Copy, Clone)]
+pub(crate) struct Coercions {
+    bits: u8,
+}
+
+impl Coercions {
+    pub const NONE: Self = Coercions { bits: 0 };
+    pub const PARSE_NUMERIC_STRINGS: Self = Coercions { bits: 1 << 0 };
+    pub const FLOAT_TO_INT: Self = Coercions { bits: 1 << 1 };
+    pub const STRICT_CHAR_AND_STRING: Self = Coercions { bits: 1 << 2 };
+    pub const BYTES_AS_STR: Self = Coercions { bits: 1 << 3 };
+    pub const SCALAR_AS_SEQ: Self = Coercions { bits: 1 << 4 };
+    pub const UNWRAP_SINGLETON_SEQ: Self = Coercions { bits: 1 << 5 };
+    pub const PAIRS_AS_MAP: Self = Coercions { bits: 1 << 6 };
+    pub const INDEX_MAP_AS_SEQ: Self = Coercions { bits: 1 << 7 };
+
+    pub const fn contains(self, other: Self) -> bool {
+        self.bits & other.bits == other.bits
+    }
+
+    pub fn insert(&mut self, other: Self) {
+        self.bits |= other.bits;
+    }
+
+    pub fn remove(&mut self, other: Self) {
+        self.bits &= !other.bits;
+    }
+}
@@ -0,0 +1,81 @@
+use crate::error::{self, Error};
+use alloc::boxed::Box;
+use serde::de::{Deserialize, Deserializer, Visitor};
+
+pub struct ValueDeserializer<'access, 'de> {
+    erased: Box<dyn erased_serde::Deserializer<'de> + 'access>,
+}
+
+impl<'access, 'de> ValueDeserializer<'access, 'de> {
+    pub(crate) fn new<D>(deserializer: D) -> Self
+    where
+        D: Deserializer<'de> + 'access,
+    {
+        ValueDeserializer {
+            erased: Box::new(<dyn erased_serde::Deserializer>::erase(deserializer)),
+        }
+    }
+
+    /// Shorthand for `T::deserialize(self)`.
+    pub fn deserialize<T>(self) -> Result<T, Error>
+    where
+        T: Deserialize<'de>,
+    {
+        T::deserialize(self)
+    }
+}
+
+macro_rules! forward_deserialize {
+    ($($method:ident($($arg:ident: $ty:ty),*))*) => {
+        $(
+            fn $method<V>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                self.erased.$method($($arg,)* visitor).map_err(error::erase)
+            }
+        )*
+    };
+}
+
+impl<'access, 'de> Deserializer<'de> for ValueDeserializer<'access, 'de> {
+    type Error = Error;
+
+    forward_deserialize! {
+        deserialize_any()
+        deserialize_bool()
+        deserialize_i8()
+        deserialize_i16()
+        deserialize_i32()
+        deserialize_i64()
+        deserialize_i128()
+        deserialize_u8()
+        deserialize_u16()
+        deserialize_u32()
+        deserialize_u64()
+        deserialize_u128()
+        deserialize_f32()
+        deserialize_f64()
+        deserialize_char()
+        deserialize_str()
+        deserialize_string()
+        deserialize_bytes()
+        deserialize_byte_buf()
+        deserialize_option()
+        deserialize_unit()
+        deserialize_unit_struct(name: &'static str)
+        deserialize_newtype_struct(name: &'static str)
+        deserialize_seq()
+        deserialize_tuple(len: usize)
+        deserialize_tuple_struct(name: &'static str, len: usize)
+        deserialize_map()
+        deserialize_struct(name: &'static str, fields: &'static [&'static str])
+        deserialize_enum(name: &'static str, variants: &'static [&'static str])
+        deserialize_identifier()
+        deserialize_ignored_any()
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.erased.is_human_readable()
+    }
+}
@@ -0,0 +1,220 @@
+use crate::any::ErasedValue;
+use crate::deserializer::ValueDeserializer;
+use crate::error::{self, Error};
+use crate::seed::ErasedDeserializeSeed;
+use alloc::boxed::Box;
+use serde::de::{Deserialize, DeserializeSeed, Deserializer, EnumAccess, VariantAccess, Visitor};
+
+trait ErasedEnumAccess<'access, 'de> {
+    fn erased_variant_seed(
+        self: Box<Self>,
+        seed: &mut dyn ErasedDeserializeSeed<'de>,
+    ) -> Result<(ErasedValue, Variant<'access, 'de>), Error>;
+}
+
+trait ErasedVariantAccess<'access, 'de> {
+    fn erased_unit_variant(self: Box<Self>) -> Result<(), Error>;
+
+    fn erased_newtype_variant_seed(
+        self: Box<Self>,
+        seed: &mut dyn ErasedDeserializeSeed<'de>,
+    ) -> Result<ErasedValue, Error>;
+
+    fn erased_tuple_variant(self: Box<Self>, len: usize) -> ValueDeserializer<'access, 'de>;
+
+    fn erased_struct_variant(
+        self: Box<Self>,
+        fields: &'static [&'static str],
+    ) -> ValueDeserializer<'access, 'de>;
+}
+
+pub struct Enum<'access, 'de> {
+    erased: Box<dyn ErasedEnumAccess<'access, 'de> + 'access>,
+}
+
+pub struct Variant<'access, 'de> {
+    erased: Box<dyn ErasedVariantAccess<'access, 'de> + 'access>,
+}
+
+impl<'access, 'de> Enum<'access, 'de> {
+    pub(crate) fn new<A>(data: A) -> Self
+    where
+        A: EnumAccess<'de> + 'access,
+        A::Variant: 'access,
+    {
+        Enum {
+            erased: Box::new(data),
+        }
+    }
+
+    /// Shorthand for `T::deserialize(serde::de::value::EnumAccessDeserializer::new(self))`.
+    pub fn deserialize<T>(self) -> Result<T, Error>
+    where
+        T: Deserialize<'de>,
+    {
+        T::deserialize(serde::de::value::EnumAccessDeserializer::new(self))
+    }
+}
+
+impl<'access, 'de> EnumAccess<'de> for Enum<'access, 'de> {
+    type Error = Error;
+    type Variant = Variant<'access, 'de>;
+
+    fn variant_seed<T>(self, seed: T) -> Result<(T::Value, Self::Variant), Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        self.erased
+            .erased_variant_seed(&mut Some(seed))
+            .map(|(erased_value, variant)| {
+                let value = unsafe { ErasedValue::take::<T::Value>(erased_value) };
+                (value, variant)
+            })
+    }
+}
+
+impl<'access, 'de> VariantAccess<'de> for Variant<'access, 'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        self.erased.erased_unit_variant()
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        self.erased
+            .erased_newtype_variant_seed(&mut Some(seed))
+            .map(|erased_value| unsafe { ErasedValue::take::<T::Value>(erased_value) })
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.erased
+            .erased_tuple_variant(len)
+            .deserialize_any(visitor)
+    }
+
+    fn struct_variant<V>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.erased
+            .erased_struct_variant(fields)
+            .deserialize_any(visitor)
+    }
+}
+
+impl<'access, 'de, Access> ErasedEnumAccess<'access, 'de> for Access
+where
+    Access: EnumAccess<'de> + 'access,
+    Access::Variant: 'access,
+{
+    fn erased_variant_seed(
+        self: Box<Self>,
+        seed: &mut dyn ErasedDeserializeSeed<'de>,
+    ) -> Result<(ErasedValue, Variant<'access, 'de>), Error> {
+        match (*self).variant_seed(seed) {
+            Ok((value, variant)) => Ok((
+                value,
+                Variant {
+                    erased: Box::new(variant),
+                },
+            )),
+            Err(err) => Err(error::erase(err)),
+        }
+    }
+}
+
+impl<'access, 'de, Access> ErasedVariantAccess<'access, 'de> for Access
+where
+    Access: VariantAccess<'de> + 'access,
+{
+    fn erased_unit_variant(self: Box<Self>) -> Result<(), Error> {
+        (*self).unit_variant().map_err(error::erase)
+    }
+
+    fn erased_newtype_variant_seed(
+        self: Box<Self>,
+        seed: &mut dyn ErasedDeserializeSeed<'de>,
+    ) -> Result<ErasedValue, Error> {
+        (*self).newtype_variant_seed(seed).map_err(error::erase)
+    }
+
+    fn erased_tuple_variant(self: Box<Self>, len: usize) -> ValueDeserializer<'access, 'de> {
+        ValueDeserializer::new(TupleVariant {
+            variant: *self,
+            len,
+        })
+    }
+
+    fn erased_struct_variant(
+        self: Box<Self>,
+        fields: &'static [&'static str],
+    ) -> ValueDeserializer<'access, 'de> {
+        ValueDeserializer::new(StructVariant {
+            variant: *self,
+            fields,
+        })
+    }
+}
+
+// Adapters that let a tuple or struct variant be driven by the erased visitor
+// inside of ValueDeserializer.
+
+struct TupleVariant<A> {
+    variant: A,
+    len: usize,
+}
+
+impl<'de, A> Deserializer<'de> for TupleVariant<A>
+where
+    A: VariantAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.variant.tuple_variant(self.len, visitor)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+struct StructVariant<A> {
+    variant: A,
+    fields: &'static [&'static str],
+}
+
+impl<'de, A> Deserializer<'de> for StructVariant<A>
+where
+    A: VariantAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.variant.struct_variant(self.fields, visitor)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
@@ -0,0 +1,97 @@
+use serde::de::{Deserializer, Visitor};
+
+/// The kind of data to request from a deserializer which is not
+/// self-describing, for use with
+/// [`UntaggedEnumVisitor::deserialize_with_hint`].
+///
+/// [`UntaggedEnumVisitor::deserialize_with_hint`]: crate::UntaggedEnumVisitor::deserialize_with_hint
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Hint {
+    /// Request a `bool`.
+    Bool,
+    /// Request an `i8`.
+    I8,
+    /// Request an `i16`.
+    I16,
+    /// Request an `i32`.
+    I32,
+    /// Request an `i64`.
+    I64,
+    /// Request an `i128`.
+    I128,
+    /// Request a `u8`.
+    U8,
+    /// Request a `u16`.
+    U16,
+    /// Request a `u32`.
+    U32,
+    /// Request a `u64`.
+    U64,
+    /// Request a `u128`.
+    U128,
+    /// Request an `f32`.
+    F32,
+    /// Request an `f64`.
+    F64,
+    /// Request a `char`.
+    Char,
+    /// Request a string which may be borrowed from the input.
+    Str,
+    /// Request an owned string.
+    String,
+    /// Request a byte array which may be borrowed from the input.
+    Bytes,
+    /// Request an owned byte array.
+    ByteBuf,
+    /// Request an optional value, for the `none` and `some` closures.
+    ///
+    /// Formats that need a hint can only produce the content of a `Some`
+    /// through a [`some`] closure. Without one, the content is dispatched as if
+    /// the `Some` were not there, which requires a self-describing format.
+    ///
+    /// [`some`]: crate::UntaggedEnumVisitor::some
+    Option,
+    /// Request a unit.
+    Unit,
+    /// Request a sequence, for the `seq` closure or sequence routes.
+    Seq,
+    /// Request a map, for the `map` closure or map routes.
+    Map,
+}
+
+impl Hint {
+    pub(crate) fn deserialize<'de, D, V>(
+        self,
+        deserializer: D,
+        visitor: V,
+    ) -> Result<V::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+        V: Visitor<'de>,
+    {
+        match self {
+            Hint::Bool => deserializer.deserialize_bool(visitor),
+            Hint::I8 => deserializer.deserialize_i8(visitor),
+            Hint::I16 => deserializer.deserialize_i16(visitor),
+            Hint::I32 => deserializer.deserialize_i32(visitor),
+            Hint::I64 => deserializer.deserialize_i64(visitor),
+            Hint::I128 => deserializer.deserialize_i128(visitor),
+            Hint::U8 => deserializer.deserialize_u8(visitor),
+            Hint::U16 => deserializer.deserialize_u16(visitor),
+            Hint::U32 => deserializer.deserialize_u32(visitor),
+            Hint::U64 => deserializer.deserialize_u64(visitor),
+            Hint::U128 => deserializer.deserialize_u128(visitor),
+            Hint::F32 => deserializer.deserialize_f32(visitor),
+            Hint::F64 => deserializer.deserialize_f64(visitor),
+            Hint::Char => deserializer.deserialize_char(visitor),
+            Hint::Str => deserializer.deserialize_str(visitor),
+            Hint::String => deserializer.deserialize_string(visitor),
+            Hint::Bytes => deserializer.deserialize_bytes(visitor),
+            Hint::ByteBuf => deserializer.deserialize_byte_buf(visitor),
+            Hint::Option => deserializer.deserialize_option(visitor),
+            Hint::Unit => deserializer.deserialize_unit(visitor),
+            Hint::Seq => deserializer.deserialize_seq(visitor),
+            Hint::Map => deserializer.deserialize_map(visitor),
+        }
+    }
+}
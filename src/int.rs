@@ -1,20 +1,126 @@
 use crate::error;
+use crate::number::{self, Number};
+use crate::shape;
 use crate::UntaggedEnumVisitor;
-use serde::de::{Unexpected, Visitor};
+use alloc::format;
+use core::fmt;
+use serde::de::{Expected, Unexpected, Visitor};
 
-pub(crate) enum IntKind {
+/// How to deserialize integers that a float closure cannot represent exactly,
+/// for use with [`UntaggedEnumVisitor::int_to_float`].
+///
+/// [`UntaggedEnumVisitor::int_to_float`]: crate::UntaggedEnumVisitor::int_to_float
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FloatPrecision {
+    /// Round to the nearest representable float. For `f64` this loses
+    /// precision above 2<sup>53</sup>. For `f32`, integers greater in
+    /// magnitude than `f32::MAX` fail to deserialize.
+    Round,
+    /// Fail to deserialize integers that would lose precision.
+    Exact,
+    /// Convert like an `as` cast. This differs from `Round` only for `f32`,
+    /// where integers greater in magnitude than `f32::MAX` become infinity
+    /// instead of failing. For `f64` the two are the same, because every
+    /// integer up to 128 bits is within the range of `f64`.
+    Lossy,
+}
+
+/// Which integer closure to use when several could represent the input, for
+/// use with [`UntaggedEnumVisitor::integer_precedence`].
+///
+/// [`UntaggedEnumVisitor::integer_precedence`]: crate::UntaggedEnumVisitor::integer_precedence
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Precedence {
+    /// Prefer the type which the deserializer produced, then wider types of
+    /// the same signedness, then narrower ones, then types of the other
+    /// signedness from narrowest to widest. This is the default.
+    Input,
+    /// Prefer the narrowest type, and signed over unsigned of equal width.
+    Narrowest,
+    /// Prefer the widest type, and signed over unsigned of equal width.
+    Widest,
+    /// Prefer signed types over unsigned, each from narrowest to widest.
+    Signed,
+    /// Prefer unsigned types over signed, each from narrowest to widest.
+    Unsigned,
+    /// Try exactly these types in this order. Closures for types not in the
+    /// list are not used.
+    Explicit(&'static [IntKind]),
+}
+
+/// An integer type, for use with [`Precedence::Explicit`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum IntKind {
+    /// The `i8` closure.
     I8,
+    /// The `i16` closure.
     I16,
+    /// The `i32` closure.
     I32,
+    /// The `i64` closure.
     I64,
+    /// The `i128` closure.
     I128,
+    /// The `u8` closure.
     U8,
+    /// The `u16` closure.
     U16,
+    /// The `u32` closure.
     U32,
+    /// The `u64` closure.
     U64,
+    /// The `u128` closure.
     U128,
 }
 
+impl IntKind {
+    const ALL: [IntKind; 10] = [
+        IntKind::I8,
+        IntKind::I16,
+        IntKind::I32,
+        IntKind::I64,
+        IntKind::I128,
+        IntKind::U8,
+        IntKind::U16,
+        IntKind::U32,
+        IntKind::U64,
+        IntKind::U128,
+    ];
+
+    fn contains<I>(self, value: I) -> bool
+    where
+        I: Integer,
+    {
+        match self {
+            IntKind::I8 => i8::int_from(value).is_some(),
+            IntKind::I16 => i16::int_from(value).is_some(),
+            IntKind::I32 => i32::int_from(value).is_some(),
+            IntKind::I64 => i64::int_from(value).is_some(),
+            IntKind::I128 => i128::int_from(value).is_some(),
+            IntKind::U8 => u8::int_from(value).is_some(),
+            IntKind::U16 => u16::int_from(value).is_some(),
+            IntKind::U32 => u32::int_from(value).is_some(),
+            IntKind::U64 => u64::int_from(value).is_some(),
+            IntKind::U128 => u128::int_from(value).is_some(),
+        }
+    }
+
+    fn range(self) -> (i128, u128) {
+        match self {
+            IntKind::I8 => (i8::MIN.into(), i8::MAX.unsigned_abs().into()),
+            IntKind::I16 => (i16::MIN.into(), i16::MAX.unsigned_abs().into()),
+            IntKind::I32 => (i32::MIN.into(), i32::MAX.unsigned_abs().into()),
+            IntKind::I64 => (i64::MIN.into(), i64::MAX.unsigned_abs().into()),
+            IntKind::I128 => (i128::MIN, i128::MAX.unsigned_abs()),
+            IntKind::U8 => (0, u8::MAX.into()),
+            IntKind::U16 => (0, u16::MAX.into()),
+            IntKind::U32 => (0, u32::MAX.into()),
+            IntKind::U64 => (0, u64::MAX.into()),
+            IntKind::U128 => (0, u128::MAX),
+        }
+    }
+}
+
 pub(crate) trait Integer:
     Copy
     + TryInto<i8>
@@ -28,34 +134,86 @@ pub(crate) trait Integer:
     + TryInto<u64>
     + TryInto<u128>
 {
+    fn into_integer(self) -> number::Integer;
 }
 
-impl<T> Integer for T
-where
-    T: Copy,
-    i8: TryFrom<T>,
-    i16: TryFrom<T>,
-    i32: TryFrom<T>,
-    i64: TryFrom<T>,
-    i128: TryFrom<T>,
-    u8: TryFrom<T>,
-    u16: TryFrom<T>,
-    u32: TryFrom<T>,
-    u64: TryFrom<T>,
-    u128: TryFrom<T>,
-{
+macro_rules! impl_integer {
+    ($($ty:ident => $variant:ident,)*) => {
+        $(
+            impl Integer for $ty {
+                fn into_integer(self) -> number::Integer {
+                    number::Integer::$variant(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_integer! {
+    i8 => I8,
+    i16 => I16,
+    i32 => I32,
+    i64 => I64,
+    i128 => I128,
+    u8 => U8,
+    u16 => U16,
+    u32 => U32,
+    u64 => U64,
+    u128 => U128,
 }
 
 impl<'closure, 'de, Value> UntaggedEnumVisitor<'closure, 'de, Value> {
-    pub(crate) fn dispatch_integer<I, E>(
-        self,
-        value: I,
-        precedence: [IntKind; 10],
-    ) -> Result<Value, E>
+    // Whether the value would be accepted by an integer handler that the
+    // precedence allows.
+    pub(crate) fn accepts_integer<I>(&self, value: I) -> bool
+    where
+        I: Integer,
+    {
+        if self.visit_integer.is_some() || self.visit_number.is_some() {
+            return true;
+        }
+        self.precedence(&IntKind::ALL)
+            .iter()
+            .any(|&kind| self.has_handler(kind) && kind.contains(value))
+    }
+
+    fn has_handler(&self, kind: IntKind) -> bool {
+        match kind {
+            IntKind::I8 => self.visit_i8.is_some(),
+            IntKind::I16 => self.visit_i16.is_some(),
+            IntKind::I32 => self.visit_i32.is_some(),
+            IntKind::I64 => self.visit_i64.is_some(),
+            IntKind::I128 => self.visit_i128.is_some(),
+            IntKind::U8 => self.visit_u8.is_some(),
+            IntKind::U16 => self.visit_u16.is_some(),
+            IntKind::U32 => self.visit_u32.is_some(),
+            IntKind::U64 => self.visit_u64.is_some(),
+            IntKind::U128 => self.visit_u128.is_some(),
+        }
+    }
+
+    // The integer types to try, in order, given the types in the order that
+    // `Precedence::Input` would try them.
+    fn precedence<'a>(&'a self, input: &'a [IntKind]) -> &'a [IntKind] {
+        use self::IntKind::*;
+
+        match self.integer_precedence {
+            Precedence::Input => input,
+            Precedence::Narrowest => &[I8, U8, I16, U16, I32, U32, I64, U64, I128, U128],
+            Precedence::Widest => &[I128, U128, I64, U64, I32, U32, I16, U16, I8, U8],
+            Precedence::Signed => &[I8, I16, I32, I64, I128, U8, U16, U32, U64, U128],
+            Precedence::Unsigned => &[U8, U16, U32, U64, U128, I8, I16, I32, I64, I128],
+            Precedence::Explicit(kinds) => kinds,
+        }
+    }
+
+    pub(crate) fn dispatch_integer<I, E>(self, value: I, input: [IntKind; 10]) -> Result<Value, E>
     where
         I: Integer,
         E: serde::de::Error,
     {
+        let precedence = self.precedence(&input);
+        let range = self.integer_range(precedence);
         for kind in precedence {
             match kind {
                 IntKind::I8 => {
@@ -130,6 +288,37 @@ impl<'closure, 'de, Value> UntaggedEnumVisitor<'closure, 'de, Value> {
                 }
             }
         }
+        self.dispatch_unmatched_integer(value, range)
+    }
+
+    // None of the handlers for a specific integer type accepted the value.
+    fn dispatch_unmatched_integer<I, E>(self, value: I, range: Option<IntRange>) -> Result<Value, E>
+    where
+        I: Integer,
+        E: serde::de::Error,
+    {
+        if let Some(visit_integer) = self.visit_integer {
+            return visit_integer(value.into_integer()).map_err(error::unerase);
+        }
+        if let Some(visit_number) = self.visit_number {
+            let number = Number::Integer(value.into_integer());
+            return visit_number(number).map_err(error::unerase);
+        }
+        if let Some(v) = self.coerce_int_to_bool(u8::int_from(value)) {
+            return self.visit_bool(v);
+        }
+        if let Some(precision) = self.int_to_float {
+            if self.visit_f64.is_some() || self.visit_f32.is_some() {
+                return self.dispatch_int_to_float(value, precision);
+            }
+        }
+        if self.wraps_scalar() {
+            let content = shape::integer_content(value.into_integer());
+            return self.visit_singleton_seq(content);
+        }
+        if let Some(range) = range {
+            return Err(invalid_value(value, &range));
+        }
         if let Some(int) = u64::int_from(value) {
             return Err(E::invalid_type(Unexpected::Unsigned(int), &self));
         }
@@ -144,6 +333,176 @@ impl<'closure, 'de, Value> UntaggedEnumVisitor<'closure, 'de, Value> {
         }
         unreachable!()
     }
+
+    // The smallest range containing every value accepted by one of the
+    // handlers that the precedence allows.
+    fn integer_range(&self, precedence: &[IntKind]) -> Option<IntRange> {
+        let mut range: Option<IntRange> = None;
+        for &kind in precedence {
+            if self.has_handler(kind) {
+                let (min, max) = kind.range();
+                range = Some(match range {
+                    Some(range) => IntRange {
+                        min: range.min.min(min),
+                        max: range.max.max(max),
+                    },
+                    None => IntRange { min, max },
+                });
+            }
+        }
+        range
+    }
+
+    // Whether there is an integer handler that the precedence allows.
+    pub(crate) fn has_integer_handler(&self) -> bool {
+        self.visit_integer.is_some()
+            || self.visit_number.is_some()
+            || self
+                .precedence(&IntKind::ALL)
+                .iter()
+                .any(|&kind| self.has_handler(kind))
+    }
+
+    // Floats without a fractional part go through the same precedence as the
+    // integer a self-describing format would have produced for them.
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss,
+        clippy::float_cmp
+    )]
+    pub(crate) fn dispatch_float_to_int<E>(self, v: f64) -> Result<Value, E>
+    where
+        E: serde::de::Error,
+    {
+        // 2^128 and -2^127, which are exactly representable as f64.
+        const U128_END: f64 = 340_282_366_920_938_463_463_374_607_431_768_211_456.0;
+        const I128_MIN: f64 = -170_141_183_460_469_231_731_687_303_715_884_105_728.0;
+
+        if (0.0..U128_END).contains(&v) {
+            let int = v as u128;
+            if int as f64 != v {
+                let fraction = v - int as f64;
+                Err(E::invalid_value(Unexpected::Float(v), &Fraction(fraction)))
+            } else if !self.accepts_integer(int) {
+                Err(self.float_out_of_range(v))
+            } else if let Ok(int) = u64::try_from(int) {
+                self.visit_u64(int)
+            } else {
+                self.visit_u128(int)
+            }
+        } else if (I128_MIN..0.0).contains(&v) {
+            let int = v as i128;
+            if int as f64 != v {
+                let fraction = int as f64 - v;
+                Err(E::invalid_value(Unexpected::Float(v), &Fraction(fraction)))
+            } else if !self.accepts_integer(int) {
+                Err(self.float_out_of_range(v))
+            } else if let Ok(int) = i64::try_from(int) {
+                self.visit_i64(int)
+            } else {
+                self.visit_i128(int)
+            }
+        } else {
+            let expected = "an integer within the range of i128 or u128";
+            Err(E::invalid_value(Unexpected::Float(v), &expected))
+        }
+    }
+
+    // An integral float for which none of the integer handlers is wide enough.
+    fn float_out_of_range<E>(&self, v: f64) -> E
+    where
+        E: serde::de::Error,
+    {
+        match self.integer_range(self.precedence(&IntKind::ALL)) {
+            Some(range) => E::invalid_value(Unexpected::Float(v), &range),
+            None => E::invalid_type(Unexpected::Float(v), self),
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn dispatch_int_to_float<I, E>(self, value: I, precision: FloatPrecision) -> Result<Value, E>
+    where
+        I: Integer,
+        E: serde::de::Error,
+    {
+        let (negative, magnitude) = match u128::int_from(value) {
+            Some(int) => (false, int),
+            None => (true, i128::int_from(value).unwrap().unsigned_abs()),
+        };
+        let significant_bits = if magnitude == 0 {
+            0
+        } else {
+            128 - magnitude.leading_zeros() - magnitude.trailing_zeros()
+        };
+        if self.visit_f64.is_some() {
+            if precision == FloatPrecision::Exact && significant_bits > f64::MANTISSA_DIGITS {
+                let expected = "an integer exactly representable as f64";
+                return Err(invalid_value(value, &expected));
+            }
+            let float = magnitude as f64;
+            self.visit_f64(if negative { -float } else { float })
+        } else {
+            let float = magnitude as f32;
+            if precision != FloatPrecision::Lossy && float.is_infinite() {
+                let expected = "an integer within the range of f32";
+                return Err(invalid_value(value, &expected));
+            }
+            if precision == FloatPrecision::Exact && significant_bits > f32::MANTISSA_DIGITS {
+                let expected = "an integer exactly representable as f32";
+                return Err(invalid_value(value, &expected));
+            }
+            self.visit_f32(if negative { -float } else { float })
+        }
+    }
+}
+
+// "an integer; the fractional part 0.5 would be lost"
+struct Fraction(f64);
+
+impl Expected for Fraction {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "an integer; the fractional part {} would be lost",
+            self.0,
+        )
+    }
+}
+
+// "an integer between 0 and 255"
+struct IntRange {
+    min: i128,
+    max: u128,
+}
+
+impl Expected for IntRange {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "an integer between {} and {}",
+            self.min, self.max,
+        )
+    }
+}
+
+fn invalid_value<I, E>(value: I, expected: &dyn Expected) -> E
+where
+    I: Integer,
+    E: serde::de::Error,
+{
+    if let Some(int) = u64::int_from(value) {
+        E::invalid_value(Unexpected::Unsigned(int), expected)
+    } else if let Some(int) = i64::int_from(value) {
+        E::invalid_value(Unexpected::Signed(int), expected)
+    } else if let Some(int) = u128::int_from(value) {
+        let unexpected = format!("integer `{int}`");
+        E::invalid_value(Unexpected::Other(&unexpected), expected)
+    } else {
+        let int = i128::int_from(value).unwrap();
+        let unexpected = format!("integer `{int}`");
+        E::invalid_value(Unexpected::Other(&unexpected), expected)
+    }
 }
 
 trait IntFrom<I>: Sized {
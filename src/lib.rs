@@ -44,6 +44,7 @@
 //! - i8, i16, i32, i64, i128, u8, u16, u32, u64, u128
 //! - f32
 //! - f64
+//! - integer, number
 //! - char
 //! - string
 //! - borrowed\_str
@@ -51,8 +52,11 @@
 //! - borrowed\_bytes
 //! - byte\_buf
 //! - unit
+//! - some
+//! - newtype\_struct
 //! - seq
 //! - map
+//! - enum\_
 //!
 //! # Example: string or struct
 //!
@@ -205,29 +209,68 @@ extern crate alloc;
 extern crate serde_core as serde;
 
 mod any;
+mod bool_;
+mod bytes;
+mod candidates;
+mod coercion;
+mod content;
+mod deserializer;
+mod enum_;
 mod error;
+mod hint;
 mod int;
 mod map;
+mod number;
+mod parse;
+mod route;
 mod seed;
 mod seq;
+mod shape;
 
+use crate::bytes::Bytes;
+use crate::coercion::Coercions;
+use crate::content::Content;
+use crate::deserializer::ValueDeserializer;
+use crate::enum_::Enum;
 use crate::error::Error;
-use crate::map::Map;
-use crate::seq::Seq;
+use crate::map::{Map, PairsMap};
+use crate::number::{Integer, Number};
+use crate::seq::{IndexMapSeq, Seq};
+use alloc::borrow::ToOwned;
 use alloc::boxed::Box;
 use alloc::vec::Vec;
 use core::fmt::{self, Display};
 use core::marker::PhantomData;
-use serde::de::{Deserializer, Expected, MapAccess, SeqAccess, Unexpected, Visitor};
+use serde::de::value::MapAccessDeserializer;
+use serde::de::{
+    Deserialize, Deserializer, EnumAccess, Expected, MapAccess, SeqAccess, Unexpected, Visitor,
+};
+
+pub use crate::bool_::BoolCoercion;
+pub use crate::bytes::BytesCoercion;
+pub use crate::candidates::Candidates;
+pub use crate::hint::Hint;
+pub use crate::int::{FloatPrecision, IntKind, Precedence};
 
 pub mod de {
+    pub use crate::content::Content;
+    pub use crate::deserializer::ValueDeserializer;
+    pub use crate::enum_::{Enum, Variant};
     pub use crate::error::Error;
     pub use crate::map::Map;
+    pub use crate::number::{Integer, Number};
     pub use crate::seq::Seq;
 }
 
 pub struct UntaggedEnumVisitor<'closure, 'de, Value> {
     expecting: Option<Box<dyn Display + 'closure>>,
+    coercions: Coercions,
+    bool_coercion: BoolCoercion,
+    bytes_coercion: BytesCoercion,
+    int_to_float: Option<FloatPrecision>,
+    integer_precedence: Precedence,
+    human_readable: Option<Box<dyn FnOnce(Self) -> Self + 'closure>>,
+    compact: Option<Box<dyn FnOnce(Self) -> Self + 'closure>>,
     visit_bool: Option<Box<dyn FnOnce(bool) -> Result<Value, Error> + 'closure>>,
     visit_i8: Option<Box<dyn FnOnce(i8) -> Result<Value, Error> + 'closure>>,
     visit_i16: Option<Box<dyn FnOnce(i16) -> Result<Value, Error> + 'closure>>,
@@ -241,6 +284,8 @@ pub struct UntaggedEnumVisitor<'closure, 'de, Value> {
     visit_u128: Option<Box<dyn FnOnce(u128) -> Result<Value, Error> + 'closure>>,
     visit_f32: Option<Box<dyn FnOnce(f32) -> Result<Value, Error> + 'closure>>,
     visit_f64: Option<Box<dyn FnOnce(f64) -> Result<Value, Error> + 'closure>>,
+    visit_integer: Option<Box<dyn FnOnce(Integer) -> Result<Value, Error> + 'closure>>,
+    visit_number: Option<Box<dyn FnOnce(Number) -> Result<Value, Error> + 'closure>>,
     visit_char: Option<Box<dyn FnOnce(char) -> Result<Value, Error> + 'closure>>,
     visit_str: Option<Box<dyn FnOnce(&str) -> Result<Value, Error> + 'closure>>,
     visit_borrowed_str: Option<Box<dyn FnOnce(&'de str) -> Result<Value, Error> + 'closure>>,
@@ -249,16 +294,73 @@ pub struct UntaggedEnumVisitor<'closure, 'de, Value> {
     visit_byte_buf: Option<Box<dyn FnOnce(Vec<u8>) -> Result<Value, Error> + 'closure>>,
     visit_none: Option<Box<dyn FnOnce() -> Result<Value, Error> + 'closure>>,
     visit_unit: Option<Box<dyn FnOnce() -> Result<Value, Error> + 'closure>>,
+    visit_some: Option<
+        Box<
+            dyn for<'access> FnOnce(ValueDeserializer<'access, 'de>) -> Result<Value, Error>
+                + 'closure,
+        >,
+    >,
+    visit_newtype_struct: Option<
+        Box<
+            dyn for<'access> FnOnce(ValueDeserializer<'access, 'de>) -> Result<Value, Error>
+                + 'closure,
+        >,
+    >,
     visit_seq:
         Option<Box<dyn for<'access> FnOnce(Seq<'access, 'de>) -> Result<Value, Error> + 'closure>>,
+    seq_len: Vec<(
+        usize,
+        Box<dyn for<'access> FnOnce(Seq<'access, 'de>) -> Result<Value, Error> + 'closure>,
+    )>,
+    seq_by_first: Option<
+        Box<
+            dyn for<'access> FnOnce(&Content<'de>, Seq<'access, 'de>) -> Result<Value, Error>
+                + 'closure,
+        >,
+    >,
+    seq_candidates: Option<Candidates<'closure, 'de, Value>>,
     visit_map:
         Option<Box<dyn for<'access> FnOnce(Map<'access, 'de>) -> Result<Value, Error> + 'closure>>,
+    map_when_key: Vec<(
+        &'closure str,
+        Box<dyn for<'access> FnOnce(Map<'access, 'de>) -> Result<Value, Error> + 'closure>,
+    )>,
+    map_tagged: Vec<(
+        &'closure str,
+        &'closure str,
+        Box<dyn for<'access> FnOnce(Map<'access, 'de>) -> Result<Value, Error> + 'closure>,
+    )>,
+    map_adjacently_tagged: Vec<(
+        &'closure str,
+        &'closure str,
+        &'closure str,
+        Box<
+            dyn for<'access> FnOnce(ValueDeserializer<'access, 'de>) -> Result<Value, Error>
+                + 'closure,
+        >,
+    )>,
+    map_single_key: Option<
+        Box<
+            dyn for<'access> FnOnce(&str, ValueDeserializer<'access, 'de>) -> Result<Value, Error>
+                + 'closure,
+        >,
+    >,
+    map_candidates: Option<Candidates<'closure, 'de, Value>>,
+    visit_enum:
+        Option<Box<dyn for<'access> FnOnce(Enum<'access, 'de>) -> Result<Value, Error> + 'closure>>,
 }
 
 impl<'closure, 'de, Value> UntaggedEnumVisitor<'closure, 'de, Value> {
     pub fn new() -> Self {
         UntaggedEnumVisitor {
             expecting: None,
+            coercions: Coercions::NONE,
+            bool_coercion: BoolCoercion::NONE,
+            bytes_coercion: BytesCoercion::NONE,
+            int_to_float: None,
+            integer_precedence: Precedence::Input,
+            human_readable: None,
+            compact: None,
             visit_bool: None,
             visit_i8: None,
             visit_i16: None,
@@ -272,6 +374,8 @@ impl<'closure, 'de, Value> UntaggedEnumVisitor<'closure, 'de, Value> {
             visit_u128: None,
             visit_f32: None,
             visit_f64: None,
+            visit_integer: None,
+            visit_number: None,
             visit_char: None,
             visit_str: None,
             visit_borrowed_str: None,
@@ -280,8 +384,19 @@ impl<'closure, 'de, Value> UntaggedEnumVisitor<'closure, 'de, Value> {
             visit_byte_buf: None,
             visit_none: None,
             visit_unit: None,
+            visit_some: None,
+            visit_newtype_struct: None,
             visit_seq: None,
+            seq_len: Vec::new(),
+            seq_by_first: None,
+            seq_candidates: None,
             visit_map: None,
+            map_when_key: Vec::new(),
+            map_tagged: Vec::new(),
+            map_adjacently_tagged: Vec::new(),
+            map_single_key: None,
+            map_candidates: None,
+            visit_enum: None,
         }
     }
 
@@ -466,6 +581,91 @@ impl<'closure, 'de, Value> UntaggedEnumVisitor<'closure, 'de, Value> {
         self
     }
 
+    /// Deserialize an integer of any width, if none of the closures for a
+    /// specific integer type accepts it.
+    ///
+    /// ```
+    /// # use serde::de::{Deserialize, Deserializer};
+    /// # use serde_untagged::UntaggedEnumVisitor;
+    /// #
+    /// enum Limit {
+    ///     Bytes(u64),
+    ///     Unlimited,
+    /// }
+    ///
+    /// impl<'de> Deserialize<'de> for Limit {
+    ///     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    ///     where
+    ///         D: Deserializer<'de>,
+    ///     {
+    ///         UntaggedEnumVisitor::new()
+    ///             .integer(|int| match int.as_u64() {
+    ///                 Some(bytes) => Ok(Limit::Bytes(bytes)),
+    ///                 None => Err(serde::de::Error::custom("limit must not be negative")),
+    ///             })
+    ///             .string(|string| match string {
+    ///                 "unlimited" => Ok(Limit::Unlimited),
+    ///                 _ => Err(serde::de::Error::unknown_variant(string, &["unlimited"])),
+    ///             })
+    ///             .deserialize(deserializer)
+    ///     }
+    /// }
+    /// ```
+    #[must_use]
+    pub fn integer(
+        mut self,
+        visit: impl FnOnce(Integer) -> Result<Value, Error> + 'closure,
+    ) -> Self {
+        if self.visit_integer.is_some() {
+            panic!("UntaggedEnumVisitor::integer already set");
+        }
+        self.visit_integer = Some(Box::new(visit));
+        self
+    }
+
+    /// Choose which integer closure receives an integer that more than one of
+    /// them could represent.
+    ///
+    /// ```
+    /// # use serde::de::{Deserialize, Deserializer};
+    /// use serde_untagged::{Precedence, UntaggedEnumVisitor};
+    ///
+    /// enum Offset {
+    ///     Relative(i64),
+    ///     Absolute(u64),
+    /// }
+    ///
+    /// impl<'de> Deserialize<'de> for Offset {
+    ///     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    ///     where
+    ///         D: Deserializer<'de>,
+    ///     {
+    ///         UntaggedEnumVisitor::new()
+    ///             .i64(|offset| Ok(Offset::Relative(offset)))
+    ///             .u64(|offset| Ok(Offset::Absolute(offset)))
+    ///             // 5 is Relative; only offsets beyond i64::MAX are Absolute.
+    ///             .integer_precedence(Precedence::Signed)
+    ///             .deserialize(deserializer)
+    ///     }
+    /// }
+    /// ```
+    #[must_use]
+    pub fn integer_precedence(mut self, precedence: Precedence) -> Self {
+        self.integer_precedence = precedence;
+        self
+    }
+
+    /// Deserialize an integer or float of any width, if none of the closures
+    /// for a more specific numeric type accepts it.
+    #[must_use]
+    pub fn number(mut self, visit: impl FnOnce(Number) -> Result<Value, Error> + 'closure) -> Self {
+        if self.visit_number.is_some() {
+            panic!("UntaggedEnumVisitor::number already set");
+        }
+        self.visit_number = Some(Box::new(visit));
+        self
+    }
+
     #[must_use]
     pub fn char(mut self, visit: impl FnOnce(char) -> Result<Value, Error> + 'closure) -> Self {
         if self.visit_char.is_some() {
@@ -547,6 +747,43 @@ impl<'closure, 'de, Value> UntaggedEnumVisitor<'closure, 'de, Value> {
         self
     }
 
+    /// Deserialize the content of an explicit `Some`, for formats that
+    /// distinguish `Some(x)` from a bare `x` in untyped data. The argument
+    /// implements [`serde::Deserializer`].
+    ///
+    /// If no `some` closure is provided, the content of `Some` is dispatched
+    /// transparently to the other closures as if the `Some` were not there.
+    #[must_use]
+    pub fn some(
+        mut self,
+        visit: impl for<'access> FnOnce(ValueDeserializer<'access, 'de>) -> Result<Value, Error>
+            + 'closure,
+    ) -> Self {
+        if self.visit_some.is_some() {
+            panic!("UntaggedEnumVisitor::some already set");
+        }
+        self.visit_some = Some(Box::new(visit));
+        self
+    }
+
+    /// Deserialize the content of a newtype struct. The argument implements
+    /// [`serde::Deserializer`].
+    ///
+    /// If no `newtype_struct` closure is provided, the newtype is unwrapped and
+    /// its content is dispatched to the other closures.
+    #[must_use]
+    pub fn newtype_struct(
+        mut self,
+        visit: impl for<'access> FnOnce(ValueDeserializer<'access, 'de>) -> Result<Value, Error>
+            + 'closure,
+    ) -> Self {
+        if self.visit_newtype_struct.is_some() {
+            panic!("UntaggedEnumVisitor::newtype_struct already set");
+        }
+        self.visit_newtype_struct = Some(Box::new(visit));
+        self
+    }
+
     /// Deserialize a sequence. The argument implements
     /// [`serde::de::SeqAccess`].
     #[must_use]
@@ -561,6 +798,103 @@ impl<'closure, 'de, Value> UntaggedEnumVisitor<'closure, 'de, Value> {
         self
     }
 
+    /// Deserialize a sequence using this closure if it has exactly `len`
+    /// elements.
+    ///
+    /// The length is taken from the format's size hint if it provides one.
+    /// Otherwise the whole sequence is buffered in order to count it. Sequences
+    /// of any other length are passed to the
+    /// [`seq_by_first`][Self::seq_by_first] or [`seq`][Self::seq] closure, if
+    /// there is one.
+    ///
+    /// ```
+    /// # use serde::de::{Deserialize, Deserializer};
+    /// # use serde_untagged::UntaggedEnumVisitor;
+    /// #
+    /// enum Point {
+    ///     // [x, y]
+    ///     Planar(f64, f64),
+    ///     // [x, y, z]
+    ///     Spatial(f64, f64, f64),
+    /// }
+    ///
+    /// impl<'de> Deserialize<'de> for Point {
+    ///     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    ///     where
+    ///         D: Deserializer<'de>,
+    ///     {
+    ///         UntaggedEnumVisitor::new()
+    ///             .seq_len(2, |seq| {
+    ///                 let (x, y) = seq.deserialize()?;
+    ///                 Ok(Point::Planar(x, y))
+    ///             })
+    ///             .seq_len(3, |seq| {
+    ///                 let (x, y, z) = seq.deserialize()?;
+    ///                 Ok(Point::Spatial(x, y, z))
+    ///             })
+    ///             .deserialize(deserializer)
+    ///     }
+    /// }
+    /// ```
+    #[must_use]
+    pub fn seq_len(
+        mut self,
+        len: usize,
+        visit: impl for<'access> FnOnce(Seq<'access, 'de>) -> Result<Value, Error> + 'closure,
+    ) -> Self {
+        if self.seq_len.iter().any(|(l, _)| *l == len) {
+            panic!("UntaggedEnumVisitor::seq_len already set for length {len}");
+        }
+        self.seq_len.push((len, Box::new(visit)));
+        self
+    }
+
+    /// Deserialize a non-empty sequence using this closure, which gets to look
+    /// at the first element before deciding how to deserialize the sequence.
+    ///
+    /// Only the first element is buffered. The sequence passed to the closure
+    /// still begins with that element. Empty sequences are passed to the
+    /// [`seq`][Self::seq] closure, if there is one.
+    ///
+    /// ```
+    /// # use serde::de::{Deserialize, Deserializer};
+    /// use serde_untagged::de::Content;
+    /// use serde_untagged::UntaggedEnumVisitor;
+    ///
+    /// enum Step {
+    ///     // ["cmd", args...]
+    ///     Command(Vec<String>),
+    ///     // [[...], [...]]
+    ///     Pipeline(Vec<Vec<String>>),
+    /// }
+    ///
+    /// impl<'de> Deserialize<'de> for Step {
+    ///     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    ///     where
+    ///         D: Deserializer<'de>,
+    ///     {
+    ///         UntaggedEnumVisitor::new()
+    ///             .seq_by_first(|first, seq| match first {
+    ///                 Content::Seq(_) => seq.deserialize().map(Step::Pipeline),
+    ///                 _ => seq.deserialize().map(Step::Command),
+    ///             })
+    ///             .deserialize(deserializer)
+    ///     }
+    /// }
+    /// ```
+    #[must_use]
+    pub fn seq_by_first(
+        mut self,
+        visit: impl for<'access> FnOnce(&Content<'de>, Seq<'access, 'de>) -> Result<Value, Error>
+            + 'closure,
+    ) -> Self {
+        if self.seq_by_first.is_some() {
+            panic!("UntaggedEnumVisitor::seq_by_first already set");
+        }
+        self.seq_by_first = Some(Box::new(visit));
+        self
+    }
+
     /// Deserialize a key-value map. The argument implements
     /// [`serde::de::MapAccess`].
     ///
@@ -588,8 +922,8 @@ impl<'closure, 'de, Value> UntaggedEnumVisitor<'closure, 'de, Value> {
     /// ```
     ///
     /// If you need to inspect the contents of the map to decide how to
-    /// deserialize, you can buffer it into some kind of `Value` and deserialize
-    /// from there.
+    /// deserialize, you can buffer it into [`de::Content`] or some other kind
+    /// of `Value` and deserialize from there.
     ///
     /// ```
     /// # use serde::de::{Deserialize, Deserializer};
@@ -632,85 +966,991 @@ impl<'closure, 'de, Value> UntaggedEnumVisitor<'closure, 'de, Value> {
         self
     }
 
-    pub fn deserialize<D>(self, deserializer: D) -> Result<Value, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        deserializer.deserialize_any(self)
-    }
-}
-
-impl<'closure, 'de, Value> Visitor<'de> for UntaggedEnumVisitor<'closure, 'de, Value> {
-    type Value = Value;
-
-    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        if let Some(expecting) = &self.expecting {
-            return expecting.fmt(formatter);
+    /// Deserialize a key-value map using this closure if the map contains the
+    /// given key.
+    ///
+    /// Entries are read from the map and buffered only until the first key
+    /// that has been registered with `map_when_key` is found. The closure
+    /// receives a map which replays the buffered entries followed by the rest
+    /// of the input. Maps that contain none of the registered keys are passed
+    /// to the [`map`][Self::map] closure, if there is one.
+    ///
+    /// ```
+    /// # use serde::de::{Deserialize, Deserializer};
+    /// # use serde_derive::Deserialize;
+    /// # use serde_untagged::UntaggedEnumVisitor;
+    /// #
+    /// enum Response {
+    ///     // {"failure":"..."}
+    ///     Failure(Failure),
+    ///     // Anything else. {"ok":200}
+    ///     Success(serde_json::Value),
+    /// }
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Failure {
+    ///     failure: String,
+    /// }
+    ///
+    /// impl<'de> Deserialize<'de> for Response {
+    ///     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    ///     where
+    ///         D: Deserializer<'de>,
+    ///     {
+    ///         UntaggedEnumVisitor::new()
+    ///             .map_when_key("failure", |map| map.deserialize().map(Response::Failure))
+    ///             .map(|map| map.deserialize().map(Response::Success))
+    ///             .deserialize(deserializer)
+    ///     }
+    /// }
+    /// ```
+    #[must_use]
+    pub fn map_when_key(
+        mut self,
+        key: &'closure str,
+        visit: impl for<'access> FnOnce(Map<'access, 'de>) -> Result<Value, Error> + 'closure,
+    ) -> Self {
+        if self.map_when_key.iter().any(|(k, _)| *k == key) {
+            panic!("UntaggedEnumVisitor::map_when_key already set for key {key:?}");
         }
+        self.map_when_key.push((key, Box::new(visit)));
+        self
+    }
 
-        // "a string or array"
-        // "an integer, string, or map"
-        let mut message = Expecting::new(formatter);
-        if self.visit_bool.is_some() {
-            message.push("a", "boolean")?;
-        }
-        if self.visit_i8.is_some()
-            || self.visit_i16.is_some()
-            || self.visit_i32.is_some()
-            || self.visit_i64.is_some()
-            || self.visit_i128.is_some()
-            || self.visit_u8.is_some()
-            || self.visit_u16.is_some()
-            || self.visit_u32.is_some()
-            || self.visit_u64.is_some()
-            || self.visit_u128.is_some()
-        {
-            message.push("an", "integer")?;
-        }
-        if self.visit_f32.is_some() || self.visit_f64.is_some() {
-            message.push("a", "float")?;
-        }
-        if self.visit_char.is_some() {
-            message.push("a", "character")?;
-        }
-        if self.visit_str.is_some() {
-            message.push("a", "string")?;
-        }
-        if self.visit_borrowed_str.is_some() && self.visit_str.is_none() {
-            message.push("a", "borrowed string")?;
-        }
-        if self.visit_bytes.is_some()
-            || self.visit_borrowed_bytes.is_some()
-            || self.visit_byte_buf.is_some()
+    /// Deserialize an internally tagged key-value map using this closure if
+    /// the field `tag` holds the string `variant`.
+    ///
+    /// The tag field may appear anywhere in the map. Entries before it are
+    /// buffered. The closure receives a map containing every entry except the
+    /// tag. Maps whose tag holds an unrecognized string, or which lack the tag,
+    /// are passed to the [`map`][Self::map] closure if there is one.
+    ///
+    /// ```
+    /// # use serde::de::{Deserialize, Deserializer};
+    /// # use serde_derive::Deserialize;
+    /// # use serde_untagged::UntaggedEnumVisitor;
+    /// #
+    /// pub enum Compression {
+    ///     // "thin"
+    ///     Thin,
+    ///     // {"type": "thin", "jobs": 8}
+    ///     ThinWithOptions(ThinOptions),
+    ///     // {"type": "fat", "level": 3}
+    ///     Fat(FatOptions),
+    /// }
+    ///
+    /// #[derive(Deserialize)]
+    /// pub struct ThinOptions {
+    ///     pub jobs: u32,
+    /// }
+    ///
+    /// #[derive(Deserialize)]
+    /// pub struct FatOptions {
+    ///     pub level: u32,
+    /// }
+    ///
+    /// impl<'de> Deserialize<'de> for Compression {
+    ///     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    ///     where
+    ///         D: Deserializer<'de>,
+    ///     {
+    ///         UntaggedEnumVisitor::new()
+    ///             .string(|string| match string {
+    ///                 "thin" => Ok(Compression::Thin),
+    ///                 _ => Err(serde::de::Error::unknown_variant(string, &["thin"])),
+    ///             })
+    ///             .map_tagged("type", "thin", |map| {
+    ///                 map.deserialize().map(Compression::ThinWithOptions)
+    ///             })
+    ///             .map_tagged("type", "fat", |map| map.deserialize().map(Compression::Fat))
+    ///             .deserialize(deserializer)
+    ///     }
+    /// }
+    /// ```
+    #[must_use]
+    pub fn map_tagged(
+        mut self,
+        tag: &'closure str,
+        variant: &'closure str,
+        visit: impl for<'access> FnOnce(Map<'access, 'de>) -> Result<Value, Error> + 'closure,
+    ) -> Self {
+        if self
+            .map_tagged
+            .iter()
+            .any(|(t, v, _)| *t == tag && *v == variant)
         {
-            message.push("a", "byte array")?;
-        }
-        if self.visit_unit.is_some() || self.visit_none.is_some() {
-            message.push("", "null")?;
-        }
-        if self.visit_seq.is_some() {
-            message.push("an", "array")?;
+            panic!("UntaggedEnumVisitor::map_tagged already set for {tag:?} = {variant:?}");
         }
-        if self.visit_map.is_some() {
-            message.push("a", "map")?;
-        }
-        message.flush()
+        self.map_tagged.push((tag, variant, Box::new(visit)));
+        self
     }
 
-    fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E>
-    where
-        E: serde::de::Error,
-    {
-        if let Some(visit_bool) = self.visit_bool {
-            visit_bool(v).map_err(error::unerase)
-        } else {
-            DefaultVisitor::new(&self).visit_bool(v)
+    /// Deserialize an adjacently tagged key-value map using this closure if the
+    /// field `tag` holds the string `variant`. The closure receives a
+    /// [`serde::Deserializer`] for the value of the field `content`.
+    ///
+    /// The tag and content may appear in either order. If the content comes
+    /// first, it is buffered until the tag is seen. A map that has the tag but
+    /// no content is treated as having `()` as its content, and other fields
+    /// are ignored.
+    ///
+    /// ```
+    /// # use serde::de::{Deserialize, Deserializer};
+    /// # use serde_untagged::UntaggedEnumVisitor;
+    /// #
+    /// pub enum Message {
+    ///     // "ping"
+    ///     Ping,
+    ///     // {"t": "echo", "c": "..."}
+    ///     Echo(String),
+    ///     // {"c": 5, "t": "wait"}
+    ///     Wait(u64),
+    /// }
+    ///
+    /// impl<'de> Deserialize<'de> for Message {
+    ///     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    ///     where
+    ///         D: Deserializer<'de>,
+    ///     {
+    ///         UntaggedEnumVisitor::new()
+    ///             .string(|string| match string {
+    ///                 "ping" => Ok(Message::Ping),
+    ///                 _ => Err(serde::de::Error::unknown_variant(string, &["ping"])),
+    ///             })
+    ///             .map_adjacently_tagged("t", "c", "echo", |content| {
+    ///                 content.deserialize().map(Message::Echo)
+    ///             })
+    ///             .map_adjacently_tagged("t", "c", "wait", |content| {
+    ///                 content.deserialize().map(Message::Wait)
+    ///             })
+    ///             .deserialize(deserializer)
+    ///     }
+    /// }
+    /// ```
+    #[must_use]
+    pub fn map_adjacently_tagged(
+        mut self,
+        tag: &'closure str,
+        content: &'closure str,
+        variant: &'closure str,
+        visit: impl for<'access> FnOnce(ValueDeserializer<'access, 'de>) -> Result<Value, Error>
+            + 'closure,
+    ) -> Self {
+        if (self.map_adjacently_tagged.iter()).any(|(t, _, v, _)| *t == tag && *v == variant) {
+            panic!(
+                "UntaggedEnumVisitor::map_adjacently_tagged already set for {tag:?} = {variant:?}",
+            );
         }
+        self.map_adjacently_tagged
+            .push((tag, content, variant, Box::new(visit)));
+        self
     }
 
-    fn visit_i8<E>(self, v: i8) -> Result<Self::Value, E>
-    where
-        E: serde::de::Error,
+    /// Deserialize a key-value map containing exactly one entry, such as the
+    /// externally tagged `{"Variant": payload}` representation that Serde uses
+    /// for enums by default. The closure receives the key, and a
+    /// [`serde::Deserializer`] for the corresponding value.
+    ///
+    /// Maps with zero entries or more than one entry are passed to the
+    /// [`map_candidates`][Self::map_candidates] or [`map`][Self::map] closure
+    /// if there is one, and otherwise result in an error. In order to tell
+    /// how many entries there are, the map is buffered if there is such a
+    /// closure.
+    ///
+    /// ```
+    /// # use serde::de::{Deserialize, Deserializer};
+    /// # use serde_untagged::UntaggedEnumVisitor;
+    /// #
+    /// pub enum Debuginfo {
+    ///     // "off"
+    ///     Off,
+    ///     // {"level": 2}
+    ///     Level(u8),
+    ///     // {"split": "packed"}
+    ///     Split(String),
+    /// }
+    ///
+    /// impl<'de> Deserialize<'de> for Debuginfo {
+    ///     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    ///     where
+    ///         D: Deserializer<'de>,
+    ///     {
+    ///         UntaggedEnumVisitor::new()
+    ///             .string(|string| match string {
+    ///                 "off" => Ok(Debuginfo::Off),
+    ///                 _ => Err(serde::de::Error::unknown_variant(string, &["off"])),
+    ///             })
+    ///             .map_single_key(|key, value| match key {
+    ///                 "level" => value.deserialize().map(Debuginfo::Level),
+    ///                 "split" => value.deserialize().map(Debuginfo::Split),
+    ///                 _ => Err(serde::de::Error::unknown_variant(key, &["level", "split"])),
+    ///             })
+    ///             .deserialize(deserializer)
+    ///     }
+    /// }
+    /// ```
+    #[must_use]
+    pub fn map_single_key(
+        mut self,
+        visit: impl for<'access> FnOnce(&str, ValueDeserializer<'access, 'de>) -> Result<Value, Error>
+            + 'closure,
+    ) -> Self {
+        if self.map_single_key.is_some() {
+            panic!("UntaggedEnumVisitor::map_single_key already set");
+        }
+        self.map_single_key = Some(Box::new(visit));
+        self
+    }
+
+    /// Deserialize a key-value map by trying each of several types in order.
+    ///
+    /// The map is buffered into a [`de::Content`] once, and then each
+    /// candidate type is deserialized from the buffer until one succeeds. If
+    /// none of them succeed, the buffered map is passed to the
+    /// [`map`][Self::map] closure if there is one, and otherwise the error
+    /// message lists why each candidate failed.
+    ///
+    /// ```
+    /// # use serde::de::{Deserialize, Deserializer};
+    /// # use serde_derive::Deserialize;
+    /// use serde_untagged::{Candidates, UntaggedEnumVisitor};
+    ///
+    /// pub enum Dependency {
+    ///     Version(String),
+    ///     Path(PathDependency),
+    ///     Git(GitDependency),
+    /// }
+    ///
+    /// #[derive(Deserialize)]
+    /// #[serde(deny_unknown_fields)]
+    /// pub struct PathDependency {
+    ///     pub path: String,
+    /// }
+    ///
+    /// #[derive(Deserialize)]
+    /// #[serde(deny_unknown_fields)]
+    /// pub struct GitDependency {
+    ///     pub git: String,
+    ///     pub branch: Option<String>,
+    /// }
+    ///
+    /// impl<'de> Deserialize<'de> for Dependency {
+    ///     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    ///     where
+    ///         D: Deserializer<'de>,
+    ///     {
+    ///         UntaggedEnumVisitor::new()
+    ///             .string(|version| Ok(Dependency::Version(version.to_owned())))
+    ///             .map_candidates(
+    ///                 Candidates::new()
+    ///                     .try_as(Dependency::Path)
+    ///                     .try_as(Dependency::Git),
+    ///             )
+    ///             .deserialize(deserializer)
+    ///     }
+    /// }
+    /// ```
+    #[must_use]
+    pub fn map_candidates(mut self, candidates: Candidates<'closure, 'de, Value>) -> Self {
+        if self.map_candidates.is_some() {
+            panic!("UntaggedEnumVisitor::map_candidates already set");
+        }
+        self.map_candidates = Some(candidates);
+        self
+    }
+
+    /// Deserialize a sequence by trying each of several types in order. See
+    /// [`map_candidates`][Self::map_candidates]. If none of them succeed, the
+    /// buffered sequence is passed to the [`seq`][Self::seq] closure if there
+    /// is one.
+    #[must_use]
+    pub fn seq_candidates(mut self, candidates: Candidates<'closure, 'de, Value>) -> Self {
+        if self.seq_candidates.is_some() {
+            panic!("UntaggedEnumVisitor::seq_candidates already set");
+        }
+        self.seq_candidates = Some(candidates);
+        self
+    }
+
+    /// Deserialize an enum, for formats that represent enum variants in
+    /// untyped data, such as YAML tags or RON's enum syntax. The argument
+    /// implements [`serde::de::EnumAccess`].
+    ///
+    /// ```
+    /// # use serde::de::Deserializer;
+    /// use serde::de::{EnumAccess, VariantAccess};
+    /// use serde_untagged::UntaggedEnumVisitor;
+    ///
+    /// enum Shape {
+    ///     Point,
+    ///     Circle(f64),
+    /// }
+    ///
+    /// # fn deserialize<'de, D>(deserializer: D) -> Result<Shape, D::Error>
+    /// # where
+    /// #     D: Deserializer<'de>,
+    /// # {
+    /// UntaggedEnumVisitor::new()
+    ///     .enum_(|data| {
+    ///         let (variant, access) = data.variant::<String>()?;
+    ///         match variant.as_str() {
+    ///             "Circle" => access.newtype_variant().map(Shape::Circle),
+    ///             _ => access.unit_variant().map(|()| Shape::Point),
+    ///         }
+    ///     })
+    ///     .deserialize(deserializer)
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn enum_(
+        mut self,
+        visit: impl for<'access> FnOnce(Enum<'access, 'de>) -> Result<Value, Error> + 'closure,
+    ) -> Self {
+        if self.visit_enum.is_some() {
+            panic!("UntaggedEnumVisitor::enum_ already set");
+        }
+        self.visit_enum = Some(Box::new(visit));
+        self
+    }
+
+    /// Deserialize strings that look like numbers using the integer and float
+    /// closures.
+    ///
+    /// This is for formats which represent every scalar as a string, such as
+    /// environment variables, query strings and XML. Integers may have a sign,
+    /// a `0x`, `0o` or `0b` prefix, and underscores between digits. Floats are
+    /// decimal. A string which is not a number, or is a number for which no
+    /// closure is registered, is deserialized as a string.
+    ///
+    /// ```
+    /// # use serde::de::{Deserialize, Deserializer, IntoDeserializer};
+    /// # use serde_untagged::UntaggedEnumVisitor;
+    /// #
+    /// enum Listen {
+    ///     Port(u16),
+    ///     Socket(String),
+    /// }
+    ///
+    /// impl<'de> Deserialize<'de> for Listen {
+    ///     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    ///     where
+    ///         D: Deserializer<'de>,
+    ///     {
+    ///         UntaggedEnumVisitor::new()
+    ///             .u16(|port| Ok(Listen::Port(port)))
+    ///             .string(|path| Ok(Listen::Socket(path.to_owned())))
+    ///             .parse_numeric_strings()
+    ///             .deserialize(deserializer)
+    ///     }
+    /// }
+    /// #
+    /// # let deserializer = IntoDeserializer::<serde::de::value::Error>::into_deserializer("8080");
+    /// # assert!(matches!(Listen::deserialize(deserializer), Ok(Listen::Port(8080))));
+    /// ```
+    #[must_use]
+    pub fn parse_numeric_strings(mut self) -> Self {
+        self.coercions.insert(Coercions::PARSE_NUMERIC_STRINGS);
+        self
+    }
+
+    /// Deserialize some strings and integers using the `bool` closure.
+    ///
+    /// Configuration formats derived from environment variables or INI files
+    /// often spell booleans as strings like `"yes"` or `"off"`. Matching
+    /// strings are deserialized as booleans ahead of the string and numeric
+    /// string handling. Integers 0 and 1 are deserialized as booleans only if
+    /// no integer closure accepts them.
+    ///
+    /// ```
+    /// # use serde::de::{Deserialize, Deserializer};
+    /// use serde_untagged::{BoolCoercion, UntaggedEnumVisitor};
+    ///
+    /// enum Color {
+    ///     Auto(bool),
+    ///     Theme(String),
+    /// }
+    ///
+    /// impl<'de> Deserialize<'de> for Color {
+    ///     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    ///     where
+    ///         D: Deserializer<'de>,
+    ///     {
+    ///         UntaggedEnumVisitor::new()
+    ///             .bool(|enabled| Ok(Color::Auto(enabled)))
+    ///             .string(|theme| Ok(Color::Theme(theme.to_owned())))
+    ///             .bool_coercion(BoolCoercion::YES_NO | BoolCoercion::ON_OFF)
+    ///             .deserialize(deserializer)
+    ///     }
+    /// }
+    /// ```
+    #[must_use]
+    pub fn bool_coercion(mut self, coercion: BoolCoercion) -> Self {
+        self.bool_coercion = coercion;
+        self
+    }
+
+    /// Deserialize some strings and sequences using the `bytes`,
+    /// `borrowed_bytes` or `byte_buf` closure.
+    ///
+    /// Formats without a byte array type, like JSON and TOML, represent bytes
+    /// as base64 or hex strings or as arrays of integers. Strings are coerced
+    /// only if there is no `string` closure, and sequences only if there is no
+    /// `seq` closure.
+    ///
+    /// ```
+    /// # use serde::de::{Deserialize, Deserializer};
+    /// use serde_untagged::{BytesCoercion, UntaggedEnumVisitor};
+    ///
+    /// enum Key {
+    ///     Raw(Vec<u8>),
+    ///     Generate(bool),
+    /// }
+    ///
+    /// impl<'de> Deserialize<'de> for Key {
+    ///     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    ///     where
+    ///         D: Deserializer<'de>,
+    ///     {
+    ///         UntaggedEnumVisitor::new()
+    ///             .byte_buf(|key| Ok(Key::Raw(key)))
+    ///             .bool(|generate| Ok(Key::Generate(generate)))
+    ///             .bytes_coercion(BytesCoercion::HEX | BytesCoercion::SEQ)
+    ///             .deserialize(deserializer)
+    ///     }
+    /// }
+    /// ```
+    #[must_use]
+    pub fn bytes_coercion(mut self, coercion: BytesCoercion) -> Self {
+        self.bytes_coercion = coercion;
+        self
+    }
+
+    /// Deserialize byte arrays using the `string` or `borrowed_str` closure if
+    /// there are no bytes closures.
+    ///
+    /// Some producers of MessagePack, CBOR and bincode encode text as byte
+    /// arrays. The bytes must be valid UTF-8. Bytes borrowed from the input
+    /// are passed to the `borrowed_str` closure without copying.
+    #[must_use]
+    pub fn bytes_as_str(mut self) -> Self {
+        self.coercions.insert(Coercions::BYTES_AS_STR);
+        self
+    }
+
+    /// Deserialize integers using the `f64` or `f32` closure if no integer
+    /// closure accepts them.
+    ///
+    /// Without this, an input like `1` is an error for an untagged enum that
+    /// only registers float closures.
+    ///
+    /// ```
+    /// # use serde::de::{Deserialize, Deserializer};
+    /// use serde_untagged::{FloatPrecision, UntaggedEnumVisitor};
+    ///
+    /// enum Timeout {
+    ///     Seconds(f64),
+    ///     Never(bool),
+    /// }
+    ///
+    /// impl<'de> Deserialize<'de> for Timeout {
+    ///     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    ///     where
+    ///         D: Deserializer<'de>,
+    ///     {
+    ///         UntaggedEnumVisitor::new()
+    ///             .f64(|seconds| Ok(Timeout::Seconds(seconds)))
+    ///             .bool(|never| Ok(Timeout::Never(never)))
+    ///             .int_to_float(FloatPrecision::Exact)
+    ///             .deserialize(deserializer)
+    ///     }
+    /// }
+    /// ```
+    #[must_use]
+    pub fn int_to_float(mut self, precision: FloatPrecision) -> Self {
+        self.int_to_float = Some(precision);
+        self
+    }
+
+    /// Deserialize floats using the integer closures if there is no `f64`
+    /// closure.
+    ///
+    /// Some producers of JSON, YAML and TOML write `3.0` where an integer is
+    /// meant. Floats with no fractional part are dispatched to the integer
+    /// closures the same way as the corresponding integer. Other floats fail
+    /// to deserialize rather than being rounded.
+    ///
+    /// An `f32` closure does not receive `f64` input, so if the only float
+    /// closure is `f32`, floats from formats that produce `f64`, which includes
+    /// JSON, go to the integer closures.
+    ///
+    /// ```
+    /// # use serde::de::{Deserialize, Deserializer};
+    /// # use serde_untagged::UntaggedEnumVisitor;
+    /// #
+    /// enum Retries {
+    ///     Count(u32),
+    ///     Unlimited(bool),
+    /// }
+    ///
+    /// impl<'de> Deserialize<'de> for Retries {
+    ///     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    ///     where
+    ///         D: Deserializer<'de>,
+    ///     {
+    ///         UntaggedEnumVisitor::new()
+    ///             .u32(|count| Ok(Retries::Count(count)))
+    ///             .bool(|unlimited| Ok(Retries::Unlimited(unlimited)))
+    ///             .float_to_int()
+    ///             .deserialize(deserializer)
+    ///     }
+    /// }
+    /// ```
+    #[must_use]
+    pub fn float_to_int(mut self) -> Self {
+        self.coercions.insert(Coercions::FLOAT_TO_INT);
+        self
+    }
+
+    /// Do not deserialize characters using the `string` closure, or strings
+    /// using the `char` closure.
+    ///
+    /// By default, if there is no `char` closure, a character is passed to the
+    /// `string` closure. If there is a `char` closure but no `string` closure,
+    /// a string consisting of one character is passed to the `char` closure,
+    /// which is how formats like JSON that have no character type represent
+    /// characters, and longer strings are an error.
+    #[must_use]
+    pub fn strict_char_and_string(mut self) -> Self {
+        self.coercions.insert(Coercions::STRICT_CHAR_AND_STRING);
+        self
+    }
+
+    /// Deserialize a value for which there is no closure, other than none or
+    /// unit, using the `seq` closure as a sequence containing just that value.
+    ///
+    /// This is the common "string or list of strings" pattern in config files.
+    ///
+    /// ```
+    /// # use serde::de::{Deserialize, Deserializer};
+    /// use serde_untagged::UntaggedEnumVisitor;
+    ///
+    /// struct Authors(Vec<String>);
+    ///
+    /// impl<'de> Deserialize<'de> for Authors {
+    ///     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    ///     where
+    ///         D: Deserializer<'de>,
+    ///     {
+    ///         UntaggedEnumVisitor::new()
+    ///             .seq(|seq| seq.deserialize().map(Authors))
+    ///             .scalar_as_seq()
+    ///             .deserialize(deserializer)
+    ///     }
+    /// }
+    /// ```
+    #[must_use]
+    pub fn scalar_as_seq(mut self) -> Self {
+        self.coercions.insert(Coercions::SCALAR_AS_SEQ);
+        self
+    }
+
+    /// Deserialize a sequence for which there is no closure, if it contains
+    /// exactly one element, as if the input had been that element.
+    ///
+    /// Formats derived from XML or query strings sometimes produce `["1"]`
+    /// where `"1"` was meant. Only one level of nesting is unwrapped.
+    #[must_use]
+    pub fn unwrap_singleton_seq(mut self) -> Self {
+        self.coercions.insert(Coercions::UNWRAP_SINGLETON_SEQ);
+        self
+    }
+
+    /// Deserialize a sequence for which there is no closure using the `map`
+    /// closure, if each element is a key-value pair.
+    ///
+    /// A pair is either a sequence of two elements, like `["k", v]`, or a map
+    /// with exactly the entries `"key"` and `"value"`, like `{"key": "k",
+    /// "value": v}`. Some producers write maps this way when their keys are not
+    /// strings.
+    ///
+    /// ```
+    /// # use serde::de::{Deserialize, Deserializer};
+    /// use serde_untagged::UntaggedEnumVisitor;
+    /// use std::collections::BTreeMap;
+    ///
+    /// struct Headers(BTreeMap<String, String>);
+    ///
+    /// impl<'de> Deserialize<'de> for Headers {
+    ///     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    ///     where
+    ///         D: Deserializer<'de>,
+    ///     {
+    ///         UntaggedEnumVisitor::new()
+    ///             .map(|map| map.deserialize().map(Headers))
+    ///             .pairs_as_map()
+    ///             .deserialize(deserializer)
+    ///     }
+    /// }
+    /// ```
+    #[must_use]
+    pub fn pairs_as_map(mut self) -> Self {
+        self.coercions.insert(Coercions::PAIRS_AS_MAP);
+        self
+    }
+
+    /// Deserialize a map for which there is no closure using the `seq`
+    /// closure, if its keys are the indices `0`, `1`, `2`, ... in order.
+    ///
+    /// The keys may be integers or strings of decimal digits, as in
+    /// `{"0": a, "1": b}` which is how JSON derived from PHP arrays or XML
+    /// sometimes represents a list. This takes precedence over
+    /// [`scalar_as_seq`](Self::scalar_as_seq) for maps.
+    ///
+    /// ```
+    /// # use serde::de::{Deserialize, Deserializer};
+    /// use serde_untagged::UntaggedEnumVisitor;
+    ///
+    /// struct Tags(Vec<String>);
+    ///
+    /// impl<'de> Deserialize<'de> for Tags {
+    ///     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    ///     where
+    ///         D: Deserializer<'de>,
+    ///     {
+    ///         UntaggedEnumVisitor::new()
+    ///             .seq(|seq| seq.deserialize().map(Tags))
+    ///             .index_map_as_seq()
+    ///             .deserialize(deserializer)
+    ///     }
+    /// }
+    ///
+    /// let j = r#" {"0": "rust", "1": "serde"} "#;
+    /// let tags: Tags = serde_json::from_str(j).unwrap();
+    /// assert_eq!(tags.0, ["rust", "serde"]);
+    /// ```
+    #[must_use]
+    pub fn index_map_as_seq(mut self) -> Self {
+        self.coercions.insert(Coercions::INDEX_MAP_AS_SEQ);
+        self
+    }
+
+    /// Register additional handlers which apply only if the deserializer is
+    /// human readable, such as JSON or TOML.
+    ///
+    /// The closure receives this visitor and returns it with more handlers
+    /// added. It is called from [`deserialize`][Self::deserialize] once the
+    /// deserializer is known.
+    ///
+    /// ```
+    /// # use serde::de::{Deserialize, Deserializer};
+    /// # use serde_untagged::UntaggedEnumVisitor;
+    /// #
+    /// struct Address([u8; 4]);
+    ///
+    /// impl<'de> Deserialize<'de> for Address {
+    ///     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    ///     where
+    ///         D: Deserializer<'de>,
+    ///     {
+    ///         UntaggedEnumVisitor::new()
+    ///             .human_readable(|visitor| {
+    ///                 visitor.string(|string| {
+    ///                     let mut octets = [0; 4];
+    ///                     let mut parts = string.split('.');
+    ///                     for octet in &mut octets {
+    ///                         let part = parts.next().unwrap_or_default();
+    ///                         *octet = part.parse().map_err(serde::de::Error::custom)?;
+    ///                     }
+    ///                     Ok(Address(octets))
+    ///                 })
+    ///             })
+    ///             .compact(|visitor| {
+    ///                 visitor.bytes(|bytes| {
+    ///                     let octets = bytes.try_into().map_err(serde::de::Error::custom)?;
+    ///                     Ok(Address(octets))
+    ///                 })
+    ///             })
+    ///             .deserialize(deserializer)
+    ///     }
+    /// }
+    /// ```
+    #[must_use]
+    pub fn human_readable(mut self, configure: impl FnOnce(Self) -> Self + 'closure) -> Self {
+        if self.human_readable.is_some() {
+            panic!("UntaggedEnumVisitor::human_readable already set");
+        }
+        self.human_readable = Some(Box::new(configure));
+        self
+    }
+
+    /// Register additional handlers which apply only if the deserializer is
+    /// not human readable, such as CBOR or MessagePack. See
+    /// [`human_readable`][Self::human_readable].
+    #[must_use]
+    pub fn compact(mut self, configure: impl FnOnce(Self) -> Self + 'closure) -> Self {
+        if self.compact.is_some() {
+            panic!("UntaggedEnumVisitor::compact already set");
+        }
+        self.compact = Some(Box::new(configure));
+        self
+    }
+
+    pub fn deserialize<D>(self, deserializer: D) -> Result<Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let visitor = self.configure(&deserializer);
+        deserializer.deserialize_any(visitor)
+    }
+
+    /// Deserialize using `deserialize_struct` instead of `deserialize_any`.
+    ///
+    /// Some formats give special treatment to structs with particular names,
+    /// such as toml's `Spanned<T>` and datetime. This passes the struct name
+    /// and fields along to the deserializer, and then dispatches whatever
+    /// data it produces through the registered closures as usual.
+    ///
+    /// Formats differ in what they accept from `deserialize_struct` when the
+    /// name is not one they recognize. Most behave like `deserialize_any`, but
+    /// some, such as serde\_json, only accept maps and sequences.
+    ///
+    /// ```
+    /// use serde::de::{Deserialize, Deserializer};
+    /// use serde_derive::Deserialize;
+    /// use serde_untagged::UntaggedEnumVisitor;
+    ///
+    /// enum Setting {
+    ///     Plain(String),
+    ///     Spanned(Spanned),
+    /// }
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Spanned {
+    ///     #[serde(rename = "$__serde_spanned_private_start")]
+    ///     start: usize,
+    ///     #[serde(rename = "$__serde_spanned_private_end")]
+    ///     end: usize,
+    ///     #[serde(rename = "$__serde_spanned_private_value")]
+    ///     value: String,
+    /// }
+    ///
+    /// const NAME: &str = "$__serde_spanned_private_Spanned";
+    /// const FIELDS: &[&str] = &[
+    ///     "$__serde_spanned_private_start",
+    ///     "$__serde_spanned_private_end",
+    ///     "$__serde_spanned_private_value",
+    /// ];
+    ///
+    /// impl<'de> Deserialize<'de> for Setting {
+    ///     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    ///     where
+    ///         D: Deserializer<'de>,
+    ///     {
+    ///         UntaggedEnumVisitor::new()
+    ///             .string(|string| Ok(Setting::Plain(string.to_owned())))
+    ///             .map(|map| map.deserialize().map(Setting::Spanned))
+    ///             .deserialize_struct(deserializer, NAME, FIELDS)
+    ///     }
+    /// }
+    /// ```
+    pub fn deserialize_struct<D>(
+        self,
+        deserializer: D,
+        name: &'static str,
+        fields: &'static [&'static str],
+    ) -> Result<Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let visitor = self.configure(&deserializer);
+        deserializer.deserialize_struct(name, fields, visitor)
+    }
+
+    /// Deserialize using `deserialize_newtype_struct` instead of
+    /// `deserialize_any`, for formats that give special treatment to newtype
+    /// structs with particular names, such as serde\_json's `RawValue`.
+    ///
+    /// Register a [`newtype_struct`](Self::newtype_struct) closure to read the
+    /// content. Without one, the content is dispatched to the other closures
+    /// using `deserialize_any`, which formats that are not self-describing,
+    /// such as bincode, reject.
+    pub fn deserialize_newtype_struct<D>(
+        self,
+        deserializer: D,
+        name: &'static str,
+    ) -> Result<Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let visitor = self.configure(&deserializer);
+        deserializer.deserialize_newtype_struct(name, visitor)
+    }
+
+    /// Deserialize by asking for the given kind of data instead of using
+    /// `deserialize_any`, for formats that cannot say which kind of data comes
+    /// next, such as bincode and postcard.
+    ///
+    /// Only the closure matching the hint is reachable, so pick the
+    /// representation the type uses when it is serialized. Whether the format
+    /// is self-describing cannot be detected, so it is up to the caller to use
+    /// this only with formats that need it. Note that CBOR and MessagePack
+    /// are self-describing even though they are not human readable.
+    ///
+    /// With [`Hint::Option`], register a [`some`](Self::some) closure to read
+    /// the content of `Some`. Without one, the content is dispatched to the
+    /// other closures using `deserialize_any`, which these formats reject.
+    ///
+    /// ```
+    /// # use serde::de::{Deserialize, Deserializer};
+    /// use serde_untagged::{Hint, UntaggedEnumVisitor};
+    ///
+    /// enum Version {
+    ///     Number(u32),
+    ///     Name(String),
+    /// }
+    ///
+    /// impl<'de> Deserialize<'de> for Version {
+    ///     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    ///     where
+    ///         D: Deserializer<'de>,
+    ///     {
+    ///         let visitor = UntaggedEnumVisitor::new()
+    ///             .u32(|n| Ok(Version::Number(n)))
+    ///             .string(|s| Ok(Version::Name(s.to_owned())));
+    ///
+    ///         if deserializer.is_human_readable() {
+    ///             visitor.deserialize(deserializer)
+    ///         } else {
+    ///             // Only ever stored using bincode, as a string.
+    ///             visitor.deserialize_with_hint(deserializer, Hint::Str)
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn deserialize_with_hint<D>(self, deserializer: D, hint: Hint) -> Result<Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let visitor = self.configure(&deserializer);
+        hint.deserialize(deserializer, visitor)
+    }
+
+    fn configure<D>(mut self, deserializer: &D) -> Self
+    where
+        D: Deserializer<'de>,
+    {
+        let configure = if deserializer.is_human_readable() {
+            self.human_readable.take()
+        } else {
+            self.compact.take()
+        };
+        match configure {
+            Some(configure) => configure(self),
+            None => self,
+        }
+    }
+
+    // A string which was not coerced to a bool or number.
+    fn dispatch_str<E>(self, v: &str) -> Result<Value, E>
+    where
+        E: serde::de::Error,
+    {
+        if let Some(visit_str) = self.visit_str {
+            visit_str(v).map_err(error::unerase)
+        } else if self.visit_char.is_some()
+            && !self.coercions.contains(Coercions::STRICT_CHAR_AND_STRING)
+        {
+            let mut chars = v.chars();
+            match (chars.next(), chars.next()) {
+                (Some(ch), None) => self.visit_char(ch),
+                _ => Err(E::invalid_length(v.chars().count(), &"a single character")),
+            }
+        } else if let Some(bytes) = self.str_to_bytes(v) {
+            self.visit_coerced_bytes(bytes?)
+        } else if self.wraps_scalar() {
+            self.visit_singleton_seq(Content::String(v.to_owned()))
+        } else {
+            DefaultVisitor::new(&self).visit_str(v)
+        }
+    }
+}
+
+impl<'closure, 'de, Value> Visitor<'de> for UntaggedEnumVisitor<'closure, 'de, Value> {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(expecting) = &self.expecting {
+            return expecting.fmt(formatter);
+        }
+
+        // "a string or array"
+        // "an integer, string, or map"
+        let mut message = Expecting::new(formatter);
+        if self.visit_bool.is_some() {
+            message.push("a", "boolean")?;
+        }
+        if self.visit_number.is_some() {
+            message.push("a", "number")?;
+        } else {
+            if self.has_integer_handler() {
+                message.push("an", "integer")?;
+            }
+            if self.visit_f32.is_some() || self.visit_f64.is_some() {
+                message.push("a", "float")?;
+            }
+        }
+        if self.visit_char.is_some() {
+            message.push("a", "character")?;
+        }
+        if self.visit_str.is_some() {
+            message.push("a", "string")?;
+        }
+        if self.visit_borrowed_str.is_some() && self.visit_str.is_none() {
+            message.push("a", "borrowed string")?;
+        }
+        if self.visit_bytes.is_some()
+            || self.visit_borrowed_bytes.is_some()
+            || self.visit_byte_buf.is_some()
+        {
+            message.push("a", "byte array")?;
+        }
+        if self.visit_unit.is_some() || self.visit_none.is_some() {
+            message.push("", "null")?;
+        }
+        if self.visit_some.is_some() {
+            message.push("an", "option")?;
+        }
+        if self.visit_newtype_struct.is_some() {
+            message.push("a", "newtype struct")?;
+        }
+        if self.has_seq_handler() {
+            message.push("an", "array")?;
+        }
+        if self.has_map_handler() {
+            message.push("a", "map")?;
+        }
+        if self.visit_enum.is_some() {
+            message.push("an", "enum")?;
+        }
+        message.flush()
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        if let Some(visit_bool) = self.visit_bool {
+            visit_bool(v).map_err(error::unerase)
+        } else if self.wraps_scalar() {
+            self.visit_singleton_seq(Content::Bool(v))
+        } else {
+            DefaultVisitor::new(&self).visit_bool(v)
+        }
+    }
+
+    fn visit_i8<E>(self, v: i8) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
     {
         use crate::int::IntKind::*;
         self.dispatch_integer(v, [I8, I16, I32, I64, I128, U8, U16, U32, U64, U128])
@@ -794,6 +2034,10 @@ impl<'closure, 'de, Value> Visitor<'de> for UntaggedEnumVisitor<'closure, 'de, V
     {
         if let Some(visit_f32) = self.visit_f32 {
             visit_f32(v).map_err(error::unerase)
+        } else if self.visit_f64.is_some() {
+            self.visit_f64(f64::from(v))
+        } else if let Some(visit_number) = self.visit_number {
+            visit_number(Number::F32(v)).map_err(error::unerase)
         } else {
             self.visit_f64(f64::from(v))
         }
@@ -805,6 +2049,12 @@ impl<'closure, 'de, Value> Visitor<'de> for UntaggedEnumVisitor<'closure, 'de, V
     {
         if let Some(visit_f64) = self.visit_f64 {
             visit_f64(v).map_err(error::unerase)
+        } else if let Some(visit_number) = self.visit_number {
+            visit_number(Number::F64(v)).map_err(error::unerase)
+        } else if self.coercions.contains(Coercions::FLOAT_TO_INT) && self.has_integer_handler() {
+            self.dispatch_float_to_int(v)
+        } else if self.wraps_scalar() {
+            self.visit_singleton_seq(Content::F64(v))
         } else {
             DefaultVisitor::new(&self).visit_f64(v)
         }
@@ -816,8 +2066,12 @@ impl<'closure, 'de, Value> Visitor<'de> for UntaggedEnumVisitor<'closure, 'de, V
     {
         if let Some(visit_char) = self.visit_char {
             visit_char(v).map_err(error::unerase)
-        } else if self.visit_str.is_some() {
+        } else if self.visit_str.is_some()
+            && !self.coercions.contains(Coercions::STRICT_CHAR_AND_STRING)
+        {
             self.visit_str(v.encode_utf8(&mut [0u8; 4]))
+        } else if self.wraps_scalar() {
+            self.visit_singleton_seq(Content::Char(v))
         } else {
             Err(E::invalid_type(Unexpected::Char(v), &self))
         }
@@ -827,21 +2081,33 @@ impl<'closure, 'de, Value> Visitor<'de> for UntaggedEnumVisitor<'closure, 'de, V
     where
         E: serde::de::Error,
     {
-        if let Some(visit_str) = self.visit_str {
-            visit_str(v).map_err(error::unerase)
-        } else {
-            DefaultVisitor::new(&self).visit_str(v)
+        if let Some(parsed) = self.parse_str(v) {
+            return self.visit_parsed(parsed);
         }
+        self.dispatch_str(v)
     }
 
     fn visit_borrowed_str<E>(self, v: &'de str) -> Result<Self::Value, E>
     where
         E: serde::de::Error,
     {
+        if let Some(parsed) = self.parse_str(v) {
+            return self.visit_parsed(parsed);
+        }
         if let Some(visit_borrowed_str) = self.visit_borrowed_str {
             visit_borrowed_str(v).map_err(error::unerase)
+        } else if self.visit_str.is_some() || self.visit_char.is_some() {
+            self.dispatch_str(v)
+        } else if let Some(bytes) = self.str_to_bytes(v) {
+            let bytes = match bytes? {
+                Bytes::Transient(v) => Bytes::Borrowed(v),
+                bytes => bytes,
+            };
+            self.visit_coerced_bytes(bytes)
+        } else if self.wraps_scalar() {
+            self.visit_singleton_seq(Content::Str(v))
         } else {
-            self.visit_str(v)
+            self.dispatch_str(v)
         }
     }
 
@@ -851,6 +2117,10 @@ impl<'closure, 'de, Value> Visitor<'de> for UntaggedEnumVisitor<'closure, 'de, V
     {
         if let Some(visit_bytes) = self.visit_bytes {
             visit_bytes(v).map_err(error::unerase)
+        } else if let Some(string) = self.bytes_to_str(v) {
+            self.visit_str(string?)
+        } else if self.wraps_scalar() {
+            self.visit_singleton_seq(Content::ByteBuf(v.to_vec()))
         } else {
             DefaultVisitor::new(&self).visit_bytes(v)
         }
@@ -862,6 +2132,10 @@ impl<'closure, 'de, Value> Visitor<'de> for UntaggedEnumVisitor<'closure, 'de, V
     {
         if let Some(visit_borrowed_bytes) = self.visit_borrowed_bytes {
             visit_borrowed_bytes(v).map_err(error::unerase)
+        } else if let Some(string) = self.bytes_to_str(v) {
+            self.visit_borrowed_str(string?)
+        } else if self.visit_bytes.is_none() && self.wraps_scalar() {
+            self.visit_singleton_seq(Content::Bytes(v))
         } else {
             self.visit_bytes(v)
         }
@@ -900,12 +2174,44 @@ impl<'closure, 'de, Value> Visitor<'de> for UntaggedEnumVisitor<'closure, 'de, V
         }
     }
 
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if let Some(visit_some) = self.visit_some {
+            visit_some(ValueDeserializer::new(deserializer)).map_err(error::unerase)
+        } else {
+            deserializer.deserialize_any(self)
+        }
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if let Some(visit_newtype_struct) = self.visit_newtype_struct {
+            visit_newtype_struct(ValueDeserializer::new(deserializer)).map_err(error::unerase)
+        } else {
+            deserializer.deserialize_any(self)
+        }
+    }
+
     fn visit_seq<A>(self, seq: A) -> Result<Self::Value, A::Error>
     where
         A: SeqAccess<'de>,
     {
-        if let Some(visit_seq) = self.visit_seq {
+        if self.has_seq_routes() {
+            self.dispatch_seq(seq)
+        } else if let Some(visit_seq) = self.visit_seq {
             visit_seq(Seq::new(seq)).map_err(error::unerase)
+        } else if self.has_bytes_handler() && self.bytes_coercion.contains(BytesCoercion::SEQ) {
+            let bytes = bytes::seq_to_bytes(seq)?;
+            self.visit_coerced_bytes(Bytes::Owned(bytes))
+        } else if self.reads_pairs() {
+            let map = Map::new(PairsMap::new(seq));
+            self.visit_map(map).map_err(error::unerase)
+        } else if self.coercions.contains(Coercions::UNWRAP_SINGLETON_SEQ) {
+            self.dispatch_singleton_seq(seq)
         } else {
             DefaultVisitor::new(&self).visit_seq(seq)
         }
@@ -915,12 +2221,33 @@ impl<'closure, 'de, Value> Visitor<'de> for UntaggedEnumVisitor<'closure, 'de, V
     where
         A: MapAccess<'de>,
     {
-        if let Some(visit_map) = self.visit_map {
+        if self.has_map_routes() {
+            self.dispatch_map(map)
+        } else if let Some(visit_map) = self.visit_map {
             visit_map(Map::new(map)).map_err(error::unerase)
+        } else if let Some(visit_map_single_key) = self.map_single_key {
+            route::single_key(Map::new(map), visit_map_single_key).map_err(error::unerase)
+        } else if self.reads_index_map() {
+            let seq = Seq::new(IndexMapSeq::new(map));
+            self.visit_seq(seq).map_err(error::unerase)
+        } else if self.wraps_scalar() {
+            let content = Content::deserialize(MapAccessDeserializer::new(map))?;
+            self.visit_singleton_seq(content)
         } else {
             DefaultVisitor::new(&self).visit_map(map)
         }
     }
+
+    fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
+    where
+        A: EnumAccess<'de>,
+    {
+        if let Some(visit_enum) = self.visit_enum {
+            visit_enum(Enum::new(data)).map_err(error::unerase)
+        } else {
+            DefaultVisitor::new(&self).visit_enum(data)
+        }
+    }
 }
 
 struct DefaultVisitor<'a, E, T> {
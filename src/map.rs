@@ -1,8 +1,11 @@
 use crate::any::ErasedValue;
+use crate::content::Content;
 use crate::error::{self, Error};
 use crate::seed::ErasedDeserializeSeed;
 use alloc::boxed::Box;
-use serde::de::{Deserialize, DeserializeSeed, MapAccess};
+use alloc::vec::{self, Vec};
+use core::fmt;
+use serde::de::{Deserialize, DeserializeSeed, Expected, MapAccess, SeqAccess};
 
 trait ErasedMapAccess<'de> {
     fn erased_next_key_seed(
@@ -92,3 +95,157 @@ where
         self.size_hint()
     }
 }
+
+// MapAccess that yields some entries which were buffered while looking ahead
+// for a distinguishing key, followed by whatever remains of the original map.
+pub(crate) struct ReplayMap<'de, A> {
+    entries: vec::IntoIter<(Content<'de>, Content<'de>)>,
+    // A key which has already been read from `rest` but whose value has not.
+    key: Option<Content<'de>>,
+    value: Option<Content<'de>>,
+    rest: A,
+}
+
+impl<'de, A> ReplayMap<'de, A> {
+    pub(crate) fn new(
+        entries: Vec<(Content<'de>, Content<'de>)>,
+        key: Option<Content<'de>>,
+        rest: A,
+    ) -> Self {
+        ReplayMap {
+            entries: entries.into_iter(),
+            key,
+            value: None,
+            rest,
+        }
+    }
+}
+
+impl<'de, A> MapAccess<'de> for ReplayMap<'de, A>
+where
+    A: MapAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn next_key_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        if let Some((key, value)) = self.entries.next() {
+            self.value = Some(value);
+            seed.deserialize(key).map(Some).map_err(error::unerase)
+        } else if let Some(key) = self.key.take() {
+            seed.deserialize(key).map(Some).map_err(error::unerase)
+        } else {
+            self.rest.next_key_seed(seed)
+        }
+    }
+
+    fn next_value_seed<T>(&mut self, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        if let Some(value) = self.value.take() {
+            seed.deserialize(value).map_err(error::unerase)
+        } else {
+            self.rest.next_value_seed(seed)
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        let buffered = self.entries.len() + usize::from(self.key.is_some());
+        self.rest.size_hint().map(|rest| buffered + rest)
+    }
+}
+
+// MapAccess over a sequence whose elements are each either a `[key, value]`
+// pair or a map with "key" and "value" entries.
+pub(crate) struct PairsMap<'de, A> {
+    seq: A,
+    value: Option<Content<'de>>,
+}
+
+impl<'de, A> PairsMap<'de, A> {
+    pub(crate) fn new(seq: A) -> Self {
+        PairsMap { seq, value: None }
+    }
+}
+
+impl<'de, A> MapAccess<'de> for PairsMap<'de, A>
+where
+    A: SeqAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn next_key_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let Some(element) = self.seq.next_element::<Content<'de>>()? else {
+            return Ok(None);
+        };
+        let (key, value) = pair(element)?;
+        self.value = Some(value);
+        seed.deserialize(key).map(Some).map_err(error::unerase)
+    }
+
+    fn next_value_seed<T>(&mut self, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.value.take() {
+            Some(value) => seed.deserialize(value).map_err(error::unerase),
+            None => Err(serde::de::Error::custom("value is missing")),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.seq.size_hint()
+    }
+}
+
+struct Pair;
+
+impl Expected for Pair {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a [key, value] pair or a map with \"key\" and \"value\" entries")
+    }
+}
+
+fn pair<'de, E>(element: Content<'de>) -> Result<(Content<'de>, Content<'de>), E>
+where
+    E: serde::de::Error,
+{
+    const FIELDS: &[&str] = &["key", "value"];
+
+    match element {
+        Content::Seq(elements) => {
+            let len = elements.len();
+            let mut elements = elements.into_iter();
+            match (elements.next(), elements.next(), elements.next()) {
+                (Some(key), Some(value), None) => Ok((key, value)),
+                _ => Err(E::invalid_length(len, &Pair)),
+            }
+        }
+        Content::Map(entries) => {
+            let mut key = None;
+            let mut value = None;
+            for (k, v) in entries {
+                match k.as_str() {
+                    Some("key") if key.is_none() => key = Some(v),
+                    Some("value") if value.is_none() => value = Some(v),
+                    Some("key") => return Err(E::duplicate_field("key")),
+                    Some("value") => return Err(E::duplicate_field("value")),
+                    Some(other) => return Err(E::unknown_field(other, FIELDS)),
+                    None => return Err(E::invalid_type(k.unexpected(), &Pair)),
+                }
+            }
+            match (key, value) {
+                (Some(key), Some(value)) => Ok((key, value)),
+                (None, _) => Err(E::missing_field("key")),
+                (Some(_), None) => Err(E::missing_field("value")),
+            }
+        }
+        other => Err(E::invalid_type(other.unexpected(), &Pair)),
+    }
+}
@@ -0,0 +1,113 @@
+/// An integer of any width, as received from the deserializer.
+///
+/// Passed to [`UntaggedEnumVisitor::integer`].
+///
+/// [`UntaggedEnumVisitor::integer`]: crate::UntaggedEnumVisitor::integer
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Integer {
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    I128(i128),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    U128(u128),
+}
+
+/// An integer or float, as received from the deserializer.
+///
+/// Passed to [`UntaggedEnumVisitor::number`].
+///
+/// [`UntaggedEnumVisitor::number`]: crate::UntaggedEnumVisitor::number
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Number {
+    Integer(Integer),
+    F32(f32),
+    F64(f64),
+}
+
+macro_rules! integer_conversions {
+    ($($method:ident -> $ty:ty,)*) => {
+        impl Integer {
+            $(
+                /// Returns the integer if it fits in the target type without
+                /// truncation.
+                pub fn $method(self) -> Option<$ty> {
+                    match self {
+                        Integer::I8(int) => int.try_into().ok(),
+                        Integer::I16(int) => int.try_into().ok(),
+                        Integer::I32(int) => int.try_into().ok(),
+                        Integer::I64(int) => int.try_into().ok(),
+                        Integer::I128(int) => int.try_into().ok(),
+                        Integer::U8(int) => int.try_into().ok(),
+                        Integer::U16(int) => int.try_into().ok(),
+                        Integer::U32(int) => int.try_into().ok(),
+                        Integer::U64(int) => int.try_into().ok(),
+                        Integer::U128(int) => int.try_into().ok(),
+                    }
+                }
+            )*
+        }
+
+        impl Number {
+            $(
+                /// Returns the number if it is an integer which fits in the
+                /// target type without truncation.
+                pub fn $method(self) -> Option<$ty> {
+                    match self {
+                        Number::Integer(int) => int.$method(),
+                        Number::F32(_) | Number::F64(_) => None,
+                    }
+                }
+            )*
+        }
+    };
+}
+
+integer_conversions! {
+    as_i8 -> i8,
+    as_i16 -> i16,
+    as_i32 -> i32,
+    as_i64 -> i64,
+    as_i128 -> i128,
+    as_u8 -> u8,
+    as_u16 -> u16,
+    as_u32 -> u32,
+    as_u64 -> u64,
+    as_u128 -> u128,
+}
+
+impl Integer {
+    /// Converts to the nearest `f64`, which loses precision for integers with
+    /// a magnitude above 2<sup>53</sup>.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn as_f64_lossy(self) -> f64 {
+        match self {
+            Integer::I8(int) => f64::from(int),
+            Integer::I16(int) => f64::from(int),
+            Integer::I32(int) => f64::from(int),
+            Integer::I64(int) => int as f64,
+            Integer::I128(int) => int as f64,
+            Integer::U8(int) => f64::from(int),
+            Integer::U16(int) => f64::from(int),
+            Integer::U32(int) => f64::from(int),
+            Integer::U64(int) => int as f64,
+            Integer::U128(int) => int as f64,
+        }
+    }
+}
+
+impl Number {
+    /// Converts to the nearest `f64`, which loses precision for integers with
+    /// a magnitude above 2<sup>53</sup>.
+    pub fn as_f64_lossy(self) -> f64 {
+        match self {
+            Number::Integer(int) => int.as_f64_lossy(),
+            Number::F32(float) => f64::from(float),
+            Number::F64(float) => float,
+        }
+    }
+}
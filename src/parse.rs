@@ -0,0 +1,150 @@
+use crate::coercion::Coercions;
+use crate::UntaggedEnumVisitor;
+use alloc::string::String;
+use serde::de::Visitor;
+
+pub(crate) enum Number {
+    Unsigned(u128),
+    Negative(i128),
+}
+
+// Parse an integer in the syntax accepted by Rust integer literals, without a
+// type suffix: an optional sign, an optional 0x/0o/0b radix prefix, and digits
+// which may be separated by underscores.
+pub(crate) fn integer(string: &str) -> Option<Number> {
+    let (negative, unsigned) = match string.as_bytes().first()? {
+        b'-' => (true, &string[1..]),
+        b'+' => (false, &string[1..]),
+        _ => (false, string),
+    };
+    let (radix, digits) = match unsigned.get(..2) {
+        Some("0x" | "0X") => (16, &unsigned[2..]),
+        Some("0o" | "0O") => (8, &unsigned[2..]),
+        Some("0b" | "0B") => (2, &unsigned[2..]),
+        _ => (10, unsigned),
+    };
+    let digits = without_underscores(digits, radix)?;
+    if digits.starts_with(['+', '-']) {
+        return None;
+    }
+    let magnitude = u128::from_str_radix(&digits, radix).ok()?;
+    if negative {
+        0i128.checked_sub_unsigned(magnitude).map(Number::Negative)
+    } else {
+        Some(Number::Unsigned(magnitude))
+    }
+}
+
+// Parse a decimal floating point number, such as 3.5 or -1_000.25e-3. Unlike
+// `str::parse`, this rejects "inf" and "NaN".
+pub(crate) fn float<F>(string: &str) -> Option<F>
+where
+    F: core::str::FromStr,
+{
+    let digits = without_underscores(string, 10)?;
+    if !digits.bytes().any(|b| b.is_ascii_digit())
+        || !digits
+            .bytes()
+            .all(|b| matches!(b, b'0'..=b'9' | b'.' | b'e' | b'E' | b'+' | b'-'))
+    {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+// Underscores are allowed only between two digits of the given radix.
+fn without_underscores(string: &str, radix: u32) -> Option<String> {
+    let bytes = string.as_bytes();
+    let mut digits = String::with_capacity(string.len());
+    for (i, ch) in string.char_indices() {
+        if ch == '_' {
+            let before = i.checked_sub(1).map(|i| bytes[i]);
+            let after = bytes.get(i + 1).copied();
+            let is_digit = |b: Option<u8>| b.map_or(false, |b| char::from(b).is_digit(radix));
+            if !is_digit(before) || !is_digit(after) {
+                return None;
+            }
+        } else {
+            digits.push(ch);
+        }
+    }
+    Some(digits)
+}
+
+pub(crate) enum Numeric {
+    U64(u64),
+    I64(i64),
+    U128(u128),
+    I128(i128),
+    F32(f32),
+    F64(f64),
+}
+
+// A string coerced to a bool or number.
+pub(crate) enum Parsed {
+    Bool(bool),
+    Numeric(Numeric),
+}
+
+impl<'closure, 'de, Value> UntaggedEnumVisitor<'closure, 'de, Value> {
+    // The coercions which take precedence over the string closures, in order.
+    pub(crate) fn parse_str(&self, v: &str) -> Option<Parsed> {
+        if let Some(v) = self.parse_bool_str(v) {
+            return Some(Parsed::Bool(v));
+        }
+        if self.coercions.contains(Coercions::PARSE_NUMERIC_STRINGS) {
+            return self.parse_numeric_str(v).map(Parsed::Numeric);
+        }
+        None
+    }
+
+    pub(crate) fn visit_parsed<E>(self, parsed: Parsed) -> Result<Value, E>
+    where
+        E: serde::de::Error,
+    {
+        match parsed {
+            Parsed::Bool(v) => self.visit_bool(v),
+            Parsed::Numeric(number) => self.visit_numeric(number),
+        }
+    }
+
+    // Parse the string if it is a number for which there is a handler.
+    fn parse_numeric_str(&self, v: &str) -> Option<Numeric> {
+        match integer(v) {
+            Some(Number::Unsigned(int)) if self.accepts_integer(int) => {
+                return Some(match u64::try_from(int) {
+                    Ok(int) => Numeric::U64(int),
+                    Err(_) => Numeric::U128(int),
+                });
+            }
+            Some(Number::Negative(int)) if self.accepts_integer(int) => {
+                return Some(match i64::try_from(int) {
+                    Ok(int) => Numeric::I64(int),
+                    Err(_) => Numeric::I128(int),
+                });
+            }
+            _ => {}
+        }
+        if self.visit_f64.is_some() || self.visit_number.is_some() {
+            float(v).map(Numeric::F64)
+        } else if self.visit_f32.is_some() {
+            float(v).map(Numeric::F32)
+        } else {
+            None
+        }
+    }
+
+    fn visit_numeric<E>(self, number: Numeric) -> Result<Value, E>
+    where
+        E: serde::de::Error,
+    {
+        match number {
+            Numeric::U64(int) => self.visit_u64(int),
+            Numeric::I64(int) => self.visit_i64(int),
+            Numeric::U128(int) => self.visit_u128(int),
+            Numeric::I128(int) => self.visit_i128(int),
+            Numeric::F32(float) => self.visit_f32(float),
+            Numeric::F64(float) => self.visit_f64(float),
+        }
+    }
+}
@@ -0,0 +1,396 @@
+use crate::content::Content;
+use crate::deserializer::ValueDeserializer;
+use crate::error::{self, Error};
+use crate::map::{Map, ReplayMap};
+use crate::seq::{ReplaySeq, Seq};
+use crate::UntaggedEnumVisitor;
+use alloc::vec::Vec;
+use core::fmt;
+use serde::de::value::{MapDeserializer, SeqDeserializer};
+use serde::de::{
+    DeserializeSeed, Deserializer, Expected, IgnoredAny, MapAccess, SeqAccess, Unexpected,
+};
+
+impl<'closure, 'de, Value> UntaggedEnumVisitor<'closure, 'de, Value> {
+    pub(crate) fn has_map_handler(&self) -> bool {
+        self.visit_map.is_some() || self.map_single_key.is_some() || self.has_map_routes()
+    }
+
+    // Whether maps need to go through dispatch_map, as opposed to being passed
+    // straight to the one closure that handles maps.
+    pub(crate) fn has_map_routes(&self) -> bool {
+        !self.map_when_key.is_empty()
+            || !self.map_tagged.is_empty()
+            || !self.map_adjacently_tagged.is_empty()
+            || self.map_candidates.is_some()
+            || self.map_single_key.is_some() && self.visit_map.is_some()
+    }
+
+    // Read entries one at a time, buffering them, until reaching a key that
+    // selects a particular handler, or a tag field whose value does. The
+    // handler sees the buffered entries followed by the remainder of the map,
+    // minus the tag if there was one. If nothing selects a handler, the fully
+    // buffered map goes to the `map_single_key`, `map_candidates` or `map`
+    // handler.
+    pub(crate) fn dispatch_map<A>(mut self, mut map: A) -> Result<Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut entries = Vec::new();
+        let mut unrecognized_tag = None;
+        while let Some(key) = map.next_key::<Content<'de>>()? {
+            let Some(name) = key.as_str() else {
+                let value = map.next_value::<Content<'de>>()?;
+                entries.push((key, value));
+                continue;
+            };
+            let position = self.map_when_key.iter().position(|(k, _)| *k == name);
+            if let Some(i) = position {
+                let (_, visit) = self.map_when_key.swap_remove(i);
+                let map = ReplayMap::new(entries, Some(key), map);
+                return visit(Map::new(map)).map_err(error::unerase);
+            }
+            let tag = self.tag_field(name);
+            let value = map.next_value::<Content<'de>>()?;
+            if let Some(tag) = tag {
+                let variant = value.as_str();
+                let position = self
+                    .map_tagged
+                    .iter()
+                    .position(|(t, v, _)| *t == tag && Some(*v) == variant);
+                if let Some(i) = position {
+                    let (_, _, visit) = self.map_tagged.swap_remove(i);
+                    let map = ReplayMap::new(entries, None, map);
+                    return visit(Map::new(map)).map_err(error::unerase);
+                }
+                let position = self
+                    .map_adjacently_tagged
+                    .iter()
+                    .position(|(t, _, v, _)| *t == tag && Some(*v) == variant);
+                if let Some(i) = position {
+                    let (_, content, _, visit) = self.map_adjacently_tagged.swap_remove(i);
+                    return adjacent_content(entries, map, content, visit);
+                }
+                if unrecognized_tag.is_none() {
+                    unrecognized_tag = Some((tag, value.clone()));
+                }
+            }
+            entries.push((key, value));
+        }
+        if self.visit_map.is_some()
+            || self.map_single_key.is_some()
+            || self.map_candidates.is_some()
+        {
+            return self.visit_buffered_map(entries).map_err(error::unerase);
+        }
+        if let Some((tag, value)) = unrecognized_tag {
+            let variants = Variants {
+                tag,
+                variants: self.variants(tag),
+            };
+            return Err(serde::de::Error::invalid_value(
+                value.unexpected(),
+                &variants,
+            ));
+        }
+        let internal = self.map_tagged.first().map(|(tag, _, _)| *tag);
+        let adjacent = self
+            .map_adjacently_tagged
+            .first()
+            .map(|(tag, _, _, _)| *tag);
+        if let Some(tag) = internal.or(adjacent) {
+            Err(serde::de::Error::custom(format_args!(
+                "missing field `{tag}`"
+            )))
+        } else if !self.map_when_key.is_empty() {
+            let keys = Keys(self.map_when_key.iter().map(|(key, _)| *key).collect());
+            Err(serde::de::Error::invalid_type(Unexpected::Map, &keys))
+        } else {
+            Err(serde::de::Error::invalid_type(Unexpected::Map, &self))
+        }
+    }
+
+    // A map with exactly one entry goes to `map_single_key`. Otherwise each
+    // candidate is tried, and if none of them match, the `map` handler gets
+    // the map.
+    fn visit_buffered_map(
+        mut self,
+        entries: Vec<(Content<'de>, Content<'de>)>,
+    ) -> Result<Value, Error> {
+        if let Some(visit_map_single_key) = self.map_single_key.take() {
+            if entries.len() == 1 || self.map_candidates.is_none() && self.visit_map.is_none() {
+                let map = MapDeserializer::<_, Error>::new(entries.into_iter());
+                return single_key(Map::new(map), visit_map_single_key);
+            }
+        }
+        if let Some(candidates) = self.map_candidates.take() {
+            if self.visit_map.is_none() {
+                return candidates.deserialize(Content::Map(entries));
+            }
+            if let Ok(value) = candidates.deserialize(Content::Map(entries.clone())) {
+                return Ok(value);
+            }
+        }
+        let visit_map = self.visit_map.take().unwrap();
+        let map = MapDeserializer::<_, Error>::new(entries.into_iter());
+        visit_map(Map::new(map))
+    }
+
+    pub(crate) fn has_seq_handler(&self) -> bool {
+        self.visit_seq.is_some() || self.has_seq_routes()
+    }
+
+    pub(crate) fn has_seq_routes(&self) -> bool {
+        !self.seq_len.is_empty() || self.seq_by_first.is_some() || self.seq_candidates.is_some()
+    }
+
+    // Select a handler by the length of the sequence, using the format's size
+    // hint if it has one and otherwise buffering the whole sequence to count
+    // it. Failing that, select by the first element, and then by trying each
+    // candidate type. Whichever handler is chosen sees every element,
+    // including any that were buffered.
+    pub(crate) fn dispatch_seq<A>(mut self, mut seq: A) -> Result<Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut elements = Vec::new();
+        let mut exhausted = false;
+        let mut len = None;
+        if !self.seq_len.is_empty() {
+            let n = if let Some(n) = seq.size_hint() {
+                n
+            } else {
+                while let Some(element) = seq.next_element::<Content<'de>>()? {
+                    elements.push(element);
+                }
+                exhausted = true;
+                elements.len()
+            };
+            let position = self.seq_len.iter().position(|(l, _)| *l == n);
+            if let Some(i) = position {
+                let (_, visit) = self.seq_len.swap_remove(i);
+                let seq = ReplaySeq::new(elements, (!exhausted).then_some(seq));
+                return visit(Seq::new(seq)).map_err(error::unerase);
+            }
+            len = Some(n);
+        }
+        if let Some(visit_seq_by_first) = self.seq_by_first.take() {
+            if elements.is_empty() && !exhausted {
+                match seq.next_element::<Content<'de>>()? {
+                    Some(element) => elements.push(element),
+                    None => exhausted = true,
+                }
+            }
+            if let Some(first) = elements.first().cloned() {
+                let seq = ReplaySeq::new(elements, (!exhausted).then_some(seq));
+                return visit_seq_by_first(&first, Seq::new(seq)).map_err(error::unerase);
+            }
+        }
+        if let Some(candidates) = self.seq_candidates.take() {
+            if !exhausted {
+                while let Some(element) = seq.next_element::<Content<'de>>()? {
+                    elements.push(element);
+                }
+            }
+            let Some(visit_seq) = self.visit_seq.take() else {
+                return candidates
+                    .deserialize(Content::Seq(elements))
+                    .map_err(error::unerase);
+            };
+            if let Ok(value) = candidates.deserialize(Content::Seq(elements.clone())) {
+                return Ok(value);
+            }
+            let seq = SeqDeserializer::<_, Error>::new(elements.into_iter());
+            return visit_seq(Seq::new(seq)).map_err(error::unerase);
+        }
+        if let Some(visit_seq) = self.visit_seq.take() {
+            let seq = ReplaySeq::new(elements, (!exhausted).then_some(seq));
+            return visit_seq(Seq::new(seq)).map_err(error::unerase);
+        }
+        if let Some(len) = len {
+            let lengths = Lengths(self.seq_len.iter().map(|(l, _)| *l).collect());
+            Err(serde::de::Error::invalid_length(len, &lengths))
+        } else {
+            Err(serde::de::Error::invalid_length(0, &self))
+        }
+    }
+
+    fn tag_field(&self, name: &str) -> Option<&'closure str> {
+        let internal = self.map_tagged.iter().map(|(tag, _, _)| *tag);
+        let adjacent = self.map_adjacently_tagged.iter().map(|(tag, _, _, _)| *tag);
+        internal.chain(adjacent).find(|tag| *tag == name)
+    }
+
+    fn variants(&self, tag: &str) -> Vec<&'closure str> {
+        let internal = self.map_tagged.iter().map(|(t, v, _)| (*t, *v));
+        let adjacent = self
+            .map_adjacently_tagged
+            .iter()
+            .map(|(t, _, v, _)| (*t, *v));
+        internal
+            .chain(adjacent)
+            .filter(|(t, _)| *t == tag)
+            .map(|(_, v)| v)
+            .collect()
+    }
+}
+
+// The tag of an adjacently tagged map has been found. Look for the content,
+// either among the entries buffered before the tag, or in the rest of the map.
+// A map without content is treated as having unit content.
+fn adjacent_content<'de, A, Value>(
+    mut entries: Vec<(Content<'de>, Content<'de>)>,
+    mut map: A,
+    content: &str,
+    visit: impl for<'access> FnOnce(ValueDeserializer<'access, 'de>) -> Result<Value, Error>,
+) -> Result<Value, A::Error>
+where
+    A: MapAccess<'de>,
+{
+    let value = if let Some(i) = entries
+        .iter()
+        .position(|(k, _)| k.as_str() == Some(content))
+    {
+        let (_, content) = entries.swap_remove(i);
+        visit(ValueDeserializer::new(content)).map_err(error::unerase)?
+    } else {
+        loop {
+            let Some(key) = map.next_key::<Content<'de>>()? else {
+                break visit(ValueDeserializer::new(Content::Unit)).map_err(error::unerase)?;
+            };
+            if key.as_str() == Some(content) {
+                break map.next_value_seed(ContentSeed { visit })?;
+            }
+            map.next_value::<IgnoredAny>()?;
+        }
+    };
+    while map.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {}
+    Ok(value)
+}
+
+struct ContentSeed<F> {
+    visit: F,
+}
+
+impl<'de, F, Value> DeserializeSeed<'de> for ContentSeed<F>
+where
+    F: for<'access> FnOnce(ValueDeserializer<'access, 'de>) -> Result<Value, Error>,
+{
+    type Value = Value;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        (self.visit)(ValueDeserializer::new(deserializer)).map_err(error::unerase)
+    }
+}
+
+// "`thin` or `fat` as the value of `type`"
+struct Variants<'a> {
+    tag: &'a str,
+    variants: Vec<&'a str>,
+}
+
+impl<'a> Expected for Variants<'a> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        for (i, variant) in self.variants.iter().enumerate() {
+            if i > 0 {
+                let separator = if i + 1 < self.variants.len() {
+                    ", "
+                } else {
+                    " or "
+                };
+                formatter.write_str(separator)?;
+            }
+            write!(formatter, "`{variant}`")?;
+        }
+        write!(formatter, " as the value of `{}`", self.tag)
+    }
+}
+
+// "a map containing key `type` or `kind`"
+struct Keys<'a>(Vec<&'a str>);
+
+impl<'a> Expected for Keys<'a> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a map containing key ")?;
+        for (i, key) in self.0.iter().enumerate() {
+            if i > 0 {
+                let separator = if i + 1 < self.0.len() { ", " } else { " or " };
+                formatter.write_str(separator)?;
+            }
+            write!(formatter, "`{key}`")?;
+        }
+        Ok(())
+    }
+}
+
+// "an array of length 2 or 3"
+struct Lengths(Vec<usize>);
+
+impl Expected for Lengths {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an array of length ")?;
+        for (i, len) in self.0.iter().enumerate() {
+            if i > 0 {
+                let separator = if i + 1 < self.0.len() { ", " } else { " or " };
+                formatter.write_str(separator)?;
+            }
+            write!(formatter, "{len}")?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn single_key<'de, Value>(
+    mut map: Map<'_, 'de>,
+    visit: impl for<'access> FnOnce(&str, ValueDeserializer<'access, 'de>) -> Result<Value, Error>,
+) -> Result<Value, Error> {
+    let Some(key) = map.next_key::<Content<'de>>()? else {
+        return Err(serde::de::Error::invalid_length(0, &SingleKey));
+    };
+    let Some(key) = key.as_str() else {
+        return Err(serde::de::Error::invalid_type(
+            key.unexpected(),
+            &"a string key",
+        ));
+    };
+    let value = map.next_value_seed(SingleKeySeed { key, visit })?;
+    let mut len = 1;
+    while map.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {
+        len += 1;
+    }
+    if len == 1 {
+        Ok(value)
+    } else {
+        Err(serde::de::Error::invalid_length(len, &SingleKey))
+    }
+}
+
+struct SingleKey;
+
+impl Expected for SingleKey {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a map with a single key")
+    }
+}
+
+struct SingleKeySeed<'a, F> {
+    key: &'a str,
+    visit: F,
+}
+
+impl<'a, 'de, F, Value> DeserializeSeed<'de> for SingleKeySeed<'a, F>
+where
+    F: for<'access> FnOnce(&str, ValueDeserializer<'access, 'de>) -> Result<Value, Error>,
+{
+    type Value = Value;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        (self.visit)(self.key, ValueDeserializer::new(deserializer)).map_err(error::unerase)
+    }
+}
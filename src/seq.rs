@@ -1,8 +1,11 @@
 use crate::any::ErasedValue;
+use crate::content::Content;
 use crate::error::{self, Error};
 use crate::seed::ErasedDeserializeSeed;
 use alloc::boxed::Box;
-use serde::de::{Deserialize, DeserializeSeed, SeqAccess};
+use alloc::vec::{self, Vec};
+use core::fmt;
+use serde::de::{Deserialize, DeserializeSeed, Expected, MapAccess, SeqAccess};
 
 trait ErasedSeqAccess<'de> {
     fn erased_next_element_seed(
@@ -71,3 +74,116 @@ where
         self.size_hint()
     }
 }
+
+// SeqAccess that yields some elements which were buffered while looking ahead
+// at the length or first element, followed by whatever remains of the original
+// sequence.
+pub(crate) struct ReplaySeq<'de, A> {
+    elements: vec::IntoIter<Content<'de>>,
+    rest: Option<A>,
+}
+
+impl<'de, A> ReplaySeq<'de, A> {
+    // The rest is None if the sequence has already been read to the end.
+    pub(crate) fn new(elements: Vec<Content<'de>>, rest: Option<A>) -> Self {
+        ReplaySeq {
+            elements: elements.into_iter(),
+            rest,
+        }
+    }
+}
+
+impl<'de, A> SeqAccess<'de> for ReplaySeq<'de, A>
+where
+    A: SeqAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        if let Some(element) = self.elements.next() {
+            seed.deserialize(element).map(Some).map_err(error::unerase)
+        } else if let Some(rest) = &mut self.rest {
+            rest.next_element_seed(seed)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        let buffered = self.elements.len();
+        match &self.rest {
+            Some(rest) => rest.size_hint().map(|rest| buffered + rest),
+            None => Some(buffered),
+        }
+    }
+}
+
+// SeqAccess over a map whose keys are the indices 0, 1, 2, ... in order, as
+// either strings or integers.
+pub(crate) struct IndexMapSeq<A> {
+    map: A,
+    index: u64,
+}
+
+impl<A> IndexMapSeq<A> {
+    pub(crate) fn new(map: A) -> Self {
+        IndexMapSeq { map, index: 0 }
+    }
+}
+
+impl<'de, A> SeqAccess<'de> for IndexMapSeq<A>
+where
+    A: MapAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let Some(key) = self.map.next_key::<Content<'de>>()? else {
+            return Ok(None);
+        };
+        if !is_index(&key, self.index) {
+            let expected = Index(self.index);
+            return Err(serde::de::Error::invalid_value(key.unexpected(), &expected));
+        }
+        self.index += 1;
+        self.map.next_value_seed(seed).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.map.size_hint()
+    }
+}
+
+fn is_index(key: &Content, index: u64) -> bool {
+    if let Some(string) = key.as_str() {
+        return string.bytes().all(|b| b.is_ascii_digit())
+            && (string == "0" || !string.starts_with('0'))
+            && string.parse() == Ok(index);
+    }
+    match *key {
+        Content::U8(int) => u64::from(int) == index,
+        Content::U16(int) => u64::from(int) == index,
+        Content::U32(int) => u64::from(int) == index,
+        Content::U64(int) => int == index,
+        Content::I8(int) => u64::try_from(int) == Ok(index),
+        Content::I16(int) => u64::try_from(int) == Ok(index),
+        Content::I32(int) => u64::try_from(int) == Ok(index),
+        Content::I64(int) => u64::try_from(int) == Ok(index),
+        _ => false,
+    }
+}
+
+// "the index 3 as the next key"
+struct Index(u64);
+
+impl Expected for Index {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "the index {} as the next key", self.0)
+    }
+}
@@ -0,0 +1,73 @@
+use crate::coercion::Coercions;
+use crate::content::Content;
+use crate::error::{self, Error};
+use crate::number::Integer;
+use crate::UntaggedEnumVisitor;
+use core::iter;
+use serde::de::value::SeqDeserializer;
+use serde::de::{Deserializer, IgnoredAny, SeqAccess};
+
+impl<'closure, 'de, Value> UntaggedEnumVisitor<'closure, 'de, Value> {
+    // Whether a value for which there is no closure should be passed to the
+    // `seq` closure as a sequence of one element.
+    pub(crate) fn wraps_scalar(&self) -> bool {
+        self.coercions.contains(Coercions::SCALAR_AS_SEQ) && self.has_seq_handler()
+    }
+
+    pub(crate) fn visit_singleton_seq<E>(self, content: Content<'de>) -> Result<Value, E>
+    where
+        E: serde::de::Error,
+    {
+        let seq = SeqDeserializer::<_, Error>::new(iter::once(content));
+        serde::de::Visitor::visit_seq(self, seq).map_err(error::unerase)
+    }
+
+    // Whether a sequence for which there is no closure should be passed to the
+    // `map` closure as a sequence of key-value pairs.
+    pub(crate) fn reads_pairs(&self) -> bool {
+        self.coercions.contains(Coercions::PAIRS_AS_MAP) && self.has_map_handler()
+    }
+
+    // Whether a map for which there is no closure should be passed to the
+    // `seq` closure as a map from indices to elements.
+    pub(crate) fn reads_index_map(&self) -> bool {
+        self.coercions.contains(Coercions::INDEX_MAP_AS_SEQ) && self.has_seq_handler()
+    }
+
+    // Deserialize the only element of a sequence for which there is no closure
+    // in place of the sequence.
+    pub(crate) fn dispatch_singleton_seq<A>(mut self, mut seq: A) -> Result<Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let expected = &"a sequence of one element";
+        let Some(element) = seq.next_element::<Content<'de>>()? else {
+            return Err(serde::de::Error::invalid_length(0, expected));
+        };
+        let mut len = 1;
+        while seq.next_element::<IgnoredAny>()?.is_some() {
+            len += 1;
+        }
+        if len > 1 {
+            return Err(serde::de::Error::invalid_length(len, expected));
+        }
+        // Only one level of nesting is unwrapped.
+        self.coercions.remove(Coercions::UNWRAP_SINGLETON_SEQ);
+        element.deserialize_any(self).map_err(error::unerase)
+    }
+}
+
+pub(crate) fn integer_content<'de>(integer: Integer) -> Content<'de> {
+    match integer {
+        Integer::I8(int) => Content::I8(int),
+        Integer::I16(int) => Content::I16(int),
+        Integer::I32(int) => Content::I32(int),
+        Integer::I64(int) => Content::I64(int),
+        Integer::I128(int) => Content::I128(int),
+        Integer::U8(int) => Content::U8(int),
+        Integer::U16(int) => Content::U16(int),
+        Integer::U32(int) => Content::U32(int),
+        Integer::U64(int) => Content::U64(int),
+        Integer::U128(int) => Content::U128(int),
+    }
+}
@@ -1,6 +1,19 @@
-use serde::de::{Deserialize, Deserializer, SeqAccess};
+use serde::de::value::{
+    BorrowedBytesDeserializer, BytesDeserializer, CharDeserializer, F32Deserializer,
+    I128Deserializer, MapAccessDeserializer, MapDeserializer, SeqAccessDeserializer,
+    U128Deserializer,
+};
+use serde::de::{
+    Deserialize, Deserializer, EnumAccess, IntoDeserializer, MapAccess, SeqAccess, VariantAccess,
+    Visitor,
+};
 use serde_json::json;
-use serde_untagged::UntaggedEnumVisitor;
+use serde_untagged::de::{Content, Integer, Number};
+use serde_untagged::{
+    BoolCoercion, BytesCoercion, Candidates, FloatPrecision, Hint, IntKind, Precedence,
+    UntaggedEnumVisitor,
+};
+use std::collections::BTreeMap;
 
 #[test]
 fn test_string_or_array_string() {
@@ -151,3 +164,2597 @@ fn test_expecting() {
     let expected_message = "invalid type: null, expected foo of type T";
     assert_eq!(error.to_string(), expected_message);
 }
+
+#[test]
+fn test_some() {
+    #[derive(PartialEq, Debug)]
+    enum Value {
+        Int(u64),
+        Some(String),
+    }
+
+    let j = "1";
+    let mut de = serde_json::Deserializer::from_str(j);
+    let v = de
+        .deserialize_option(UntaggedEnumVisitor::new().u64(|int| Ok(Value::Int(int))))
+        .unwrap();
+    assert_eq!(v, Value::Int(1));
+
+    let j = r#" "..." "#;
+    let mut de = serde_json::Deserializer::from_str(j);
+    let v = de
+        .deserialize_option(
+            UntaggedEnumVisitor::new()
+                .u64(|int| Ok(Value::Int(int)))
+                .some(|deserializer| deserializer.deserialize().map(Value::Some)),
+        )
+        .unwrap();
+    assert_eq!(v, Value::Some("...".to_owned()));
+}
+
+#[test]
+fn test_newtype_struct() {
+    #[derive(PartialEq, Debug)]
+    enum Value {
+        Bool(bool),
+        Newtype(String),
+    }
+
+    let j = "true";
+    let mut de = serde_json::Deserializer::from_str(j);
+    let v = de
+        .deserialize_newtype_struct(
+            "Wrapper",
+            UntaggedEnumVisitor::new().bool(|b| Ok(Value::Bool(b))),
+        )
+        .unwrap();
+    assert_eq!(v, Value::Bool(true));
+
+    let j = r#" "..." "#;
+    let mut de = serde_json::Deserializer::from_str(j);
+    let v = de
+        .deserialize_newtype_struct(
+            "Wrapper",
+            UntaggedEnumVisitor::new()
+                .bool(|b| Ok(Value::Bool(b)))
+                .newtype_struct(|deserializer| deserializer.deserialize().map(Value::Newtype)),
+        )
+        .unwrap();
+    assert_eq!(v, Value::Newtype("...".to_owned()));
+}
+
+#[test]
+fn test_enum() {
+    #[derive(serde_derive::Deserialize, PartialEq, Debug)]
+    enum Shape {
+        Unit,
+        Newtype(u8),
+        Tuple(u8, u8),
+        Struct { x: u8 },
+    }
+
+    #[derive(PartialEq, Debug)]
+    struct Value(Shape);
+
+    impl<'de> Deserialize<'de> for Value {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            deserializer.deserialize_enum(
+                "Shape",
+                &["Unit", "Newtype", "Tuple", "Struct"],
+                UntaggedEnumVisitor::new().enum_(|data| data.deserialize().map(Value)),
+            )
+        }
+    }
+
+    let deserialize = |j: &str| serde_json::from_str::<Value>(j).unwrap().0;
+
+    assert_eq!(deserialize(r#" "Unit" "#), Shape::Unit);
+    assert_eq!(deserialize(r#" {"Newtype":1} "#), Shape::Newtype(1));
+    assert_eq!(deserialize(r#" {"Tuple":[1,2]} "#), Shape::Tuple(1, 2));
+    assert_eq!(
+        deserialize(r#" {"Struct":{"x":1}} "#),
+        Shape::Struct { x: 1 }
+    );
+
+    let j = r#" {"Circle":2} "#;
+    let mut de = serde_json::Deserializer::from_str(j);
+    let radius = de
+        .deserialize_enum(
+            "Shape",
+            &["Circle"],
+            UntaggedEnumVisitor::new().enum_(|data| {
+                let (variant, access) = data.variant::<String>()?;
+                assert_eq!(variant, "Circle");
+                access.newtype_variant::<u8>()
+            }),
+        )
+        .unwrap();
+    assert_eq!(radius, 2);
+}
+
+#[test]
+fn test_content() {
+    #[derive(serde_derive::Deserialize, PartialEq, Debug)]
+    struct Range<'a> {
+        min: &'a str,
+        max: Option<u8>,
+    }
+
+    #[derive(PartialEq, Debug)]
+    enum Value<'de> {
+        Range(Range<'de>),
+        Other(Content<'de>),
+    }
+
+    impl<'de> Deserialize<'de> for Value<'de> {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .map(|map| {
+                    let content: Content = map.deserialize()?;
+                    match Range::deserialize(content.clone()) {
+                        Ok(range) => Ok(Value::Range(range)),
+                        Err(_) => Ok(Value::Other(content)),
+                    }
+                })
+                .deserialize(deserializer)
+        }
+    }
+
+    let j = &r#" {"min":"a","max":9} "#.to_owned();
+    let v: Value = serde_json::from_str(j).unwrap();
+    assert_eq!(
+        v,
+        Value::Range(Range {
+            min: "a",
+            max: Some(9),
+        }),
+    );
+
+    let j = &r#" {"z":[true,null],"a":"..."} "#.to_owned();
+    let v: Value = serde_json::from_str(j).unwrap();
+    let expected = Content::Map(vec![
+        (
+            Content::Str("z"),
+            Content::Seq(vec![Content::Bool(true), Content::Unit]),
+        ),
+        (Content::Str("a"), Content::Str("...")),
+    ]);
+    assert_eq!(v, Value::Other(expected.clone()));
+    assert_eq!(
+        serde_json::to_string(&expected).unwrap(),
+        r#"{"z":[true,null],"a":"..."}"#,
+    );
+}
+
+#[test]
+fn test_candidates() {
+    #[derive(serde_derive::Deserialize, PartialEq, Debug)]
+    #[serde(deny_unknown_fields)]
+    struct Path {
+        path: String,
+    }
+
+    #[derive(serde_derive::Deserialize, PartialEq, Debug)]
+    #[serde(deny_unknown_fields)]
+    struct Git {
+        git: String,
+        branch: Option<String>,
+    }
+
+    #[derive(PartialEq, Debug)]
+    enum Dependency {
+        Path(Path),
+        Git(Git),
+        Versions(Vec<String>),
+        Range(u8, u8),
+    }
+
+    impl<'de> Deserialize<'de> for Dependency {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .map_candidates(
+                    Candidates::new()
+                        .try_as(Dependency::Path)
+                        .try_as(Dependency::Git),
+                )
+                .seq_candidates(
+                    Candidates::new()
+                        .try_as(|(min, max)| Dependency::Range(min, max))
+                        .try_as(Dependency::Versions),
+                )
+                .deserialize(deserializer)
+        }
+    }
+
+    let j = r#" {"path":"..."} "#;
+    let v: Dependency = serde_json::from_str(j).unwrap();
+    assert_eq!(
+        v,
+        Dependency::Path(Path {
+            path: "...".to_owned(),
+        }),
+    );
+
+    let j = r#" {"git":"...","branch":"main"} "#;
+    let v: Dependency = serde_json::from_str(j).unwrap();
+    assert_eq!(
+        v,
+        Dependency::Git(Git {
+            git: "...".to_owned(),
+            branch: Some("main".to_owned()),
+        }),
+    );
+
+    let j = " [1,2] ";
+    let v: Dependency = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Dependency::Range(1, 2));
+
+    let j = r#" ["a","z"] "#;
+    let v: Dependency = serde_json::from_str(j).unwrap();
+    assert_eq!(
+        v,
+        Dependency::Versions(vec!["a".to_owned(), "z".to_owned()]),
+    );
+
+    let j = r#" {"url":"..."} "#;
+    let error = serde_json::from_str::<Dependency>(j).unwrap_err();
+    let expected_message = "data did not match any candidate (unknown field `url`, expected `path`; unknown field `url`, expected `git` or `branch`) at line 1 column 14";
+    assert_eq!(error.to_string(), expected_message);
+}
+
+#[test]
+fn test_fallback_after_single_key_or_candidates() {
+    #[derive(serde_derive::Deserialize, PartialEq, Debug)]
+    #[serde(deny_unknown_fields)]
+    struct Range {
+        min: u8,
+        max: u8,
+    }
+
+    #[derive(PartialEq, Debug)]
+    enum Filter {
+        Exact(u8),
+        Range(Range),
+        Pair(u8, u8),
+        Other(usize),
+    }
+
+    impl<'de> Deserialize<'de> for Filter {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .map_single_key(|key, value| match key {
+                    "exact" => value.deserialize().map(Filter::Exact),
+                    _ => Err(serde::de::Error::unknown_variant(key, &["exact"])),
+                })
+                .map_candidates(Candidates::new().try_as(Filter::Range))
+                .map(|map| {
+                    let map: BTreeMap<String, u8> = map.deserialize()?;
+                    Ok(Filter::Other(map.len()))
+                })
+                .seq_candidates(Candidates::new().try_as(|(min, max)| Filter::Pair(min, max)))
+                .seq(|seq| {
+                    let seq: Vec<u8> = seq.deserialize()?;
+                    Ok(Filter::Other(seq.len()))
+                })
+                .deserialize(deserializer)
+        }
+    }
+
+    let j = r#" {"exact":1} "#;
+    let v: Filter = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Filter::Exact(1));
+
+    let j = r#" {"min":1,"max":2} "#;
+    let v: Filter = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Filter::Range(Range { min: 1, max: 2 }));
+
+    let j = r#" {"a":1,"b":2,"c":3} "#;
+    let v: Filter = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Filter::Other(3));
+
+    let j = " [1,2] ";
+    let v: Filter = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Filter::Pair(1, 2));
+
+    let j = " [1,2,3] ";
+    let v: Filter = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Filter::Other(3));
+
+    let j = r#" {"inexact":1} "#;
+    let error = serde_json::from_str::<Filter>(j).unwrap_err();
+    let expected_message = "unknown variant `inexact`, expected `exact` at line 1 column 14";
+    assert_eq!(error.to_string(), expected_message);
+}
+
+#[test]
+fn test_map_when_key() {
+    #[derive(serde_derive::Deserialize, PartialEq, Debug)]
+    struct Failure {
+        ok: Option<u16>,
+        failure: String,
+    }
+
+    #[derive(PartialEq, Debug)]
+    enum Response {
+        Success(serde_json::Value),
+        Failure(Failure),
+    }
+
+    impl<'de> Deserialize<'de> for Response {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .map_when_key("failure", |map| map.deserialize().map(Response::Failure))
+                .map(|map| map.deserialize().map(Response::Success))
+                .deserialize(deserializer)
+        }
+    }
+
+    #[derive(PartialEq, Debug)]
+    enum Shape {
+        Typed(String),
+        Kind(String),
+    }
+
+    impl<'de> Deserialize<'de> for Shape {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .map_when_key("type", |map| {
+                    let mut map: BTreeMap<String, String> = map.deserialize()?;
+                    Ok(Shape::Typed(map.remove("type").unwrap()))
+                })
+                .map_when_key("kind", |map| {
+                    let mut map: BTreeMap<String, String> = map.deserialize()?;
+                    Ok(Shape::Kind(map.remove("kind").unwrap()))
+                })
+                .deserialize(deserializer)
+        }
+    }
+
+    let j = r#" {"failure":"..."} "#;
+    let v: Response = serde_json::from_str(j).unwrap();
+    assert_eq!(
+        v,
+        Response::Failure(Failure {
+            ok: None,
+            failure: "...".to_owned(),
+        }),
+    );
+
+    let j = r#" {"ok":500,"failure":"..."} "#;
+    let v: Response = serde_json::from_str(j).unwrap();
+    assert_eq!(
+        v,
+        Response::Failure(Failure {
+            ok: Some(500),
+            failure: "...".to_owned(),
+        }),
+    );
+
+    let j = r#" {"ok":200} "#;
+    let v: Response = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Response::Success(json!({"ok":200})));
+
+    let j = r#" {"kind":"circle"} "#;
+    let v: Shape = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Shape::Kind("circle".to_owned()));
+
+    let j = r#" {"name":"circle"} "#;
+    let error = serde_json::from_str::<Shape>(j).unwrap_err();
+    let expected_message =
+        "invalid type: map, expected a map containing key `type` or `kind` at line 1 column 18";
+    assert_eq!(error.to_string(), expected_message);
+}
+
+#[test]
+fn test_map_tagged() {
+    #[derive(serde_derive::Deserialize, PartialEq, Debug)]
+    #[serde(deny_unknown_fields)]
+    struct ThinOptions {
+        jobs: u32,
+    }
+
+    #[derive(PartialEq, Debug)]
+    enum Compression {
+        Thin,
+        ThinWithOptions(ThinOptions),
+        Off,
+    }
+
+    impl<'de> Deserialize<'de> for Compression {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .string(|string| match string {
+                    "thin" => Ok(Compression::Thin),
+                    _ => Err(serde::de::Error::unknown_variant(string, &["thin"])),
+                })
+                .map_tagged("type", "thin", |map| {
+                    map.deserialize().map(Compression::ThinWithOptions)
+                })
+                .map_tagged("type", "off", |map| {
+                    map.deserialize::<serde::de::IgnoredAny>()?;
+                    Ok(Compression::Off)
+                })
+                .deserialize(deserializer)
+        }
+    }
+
+    let j = r#" "thin" "#;
+    let v: Compression = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Compression::Thin);
+
+    let j = r#" {"type":"thin","jobs":8} "#;
+    let v: Compression = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Compression::ThinWithOptions(ThinOptions { jobs: 8 }));
+
+    let j = r#" {"jobs":8,"type":"thin"} "#;
+    let v: Compression = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Compression::ThinWithOptions(ThinOptions { jobs: 8 }));
+
+    let j = r#" {"type":"off"} "#;
+    let v: Compression = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Compression::Off);
+
+    let j = r#" {"type":"fat"} "#;
+    let error = serde_json::from_str::<Compression>(j).unwrap_err();
+    let expected_message = "invalid value: string \"fat\", expected `thin` or `off` as the value of `type` at line 1 column 15";
+    assert_eq!(error.to_string(), expected_message);
+
+    let j = r#" {"jobs":8} "#;
+    let error = serde_json::from_str::<Compression>(j).unwrap_err();
+    let expected_message = "missing field `type` at line 1 column 11";
+    assert_eq!(error.to_string(), expected_message);
+}
+
+#[test]
+fn test_map_single_key() {
+    #[derive(PartialEq, Debug)]
+    enum Debuginfo {
+        Off,
+        Level(u8),
+        Split(String),
+    }
+
+    impl<'de> Deserialize<'de> for Debuginfo {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .string(|string| match string {
+                    "off" => Ok(Debuginfo::Off),
+                    _ => Err(serde::de::Error::unknown_variant(string, &["off"])),
+                })
+                .map_single_key(|key, value| match key {
+                    "level" => value.deserialize().map(Debuginfo::Level),
+                    "split" => value.deserialize().map(Debuginfo::Split),
+                    _ => Err(serde::de::Error::unknown_variant(key, &["level", "split"])),
+                })
+                .deserialize(deserializer)
+        }
+    }
+
+    // Claims 3 entries but only has 1.
+    struct InexactSizeHint(Option<(&'static str, u8)>, Option<u8>);
+
+    impl<'de> MapAccess<'de> for InexactSizeHint {
+        type Error = serde::de::value::Error;
+
+        fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+        where
+            K: serde::de::DeserializeSeed<'de>,
+        {
+            let Some((key, value)) = self.0.take() else {
+                return Ok(None);
+            };
+            self.1 = Some(value);
+            seed.deserialize(key.into_deserializer()).map(Some)
+        }
+
+        fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+        where
+            V: serde::de::DeserializeSeed<'de>,
+        {
+            seed.deserialize(self.1.take().unwrap().into_deserializer())
+        }
+
+        fn size_hint(&self) -> Option<usize> {
+            Some(3)
+        }
+    }
+
+    let j = r#" "off" "#;
+    let v: Debuginfo = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Debuginfo::Off);
+
+    let j = r#" {"level":2} "#;
+    let v: Debuginfo = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Debuginfo::Level(2));
+
+    let j = r#" {"split":"packed"} "#;
+    let v: Debuginfo = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Debuginfo::Split("packed".to_owned()));
+
+    let j = " {} ";
+    let error = serde_json::from_str::<Debuginfo>(j).unwrap_err();
+    let expected_message = "invalid length 0, expected a map with a single key at line 1 column 3";
+    assert_eq!(error.to_string(), expected_message);
+
+    let j = r#" {"level":2,"split":"packed"} "#;
+    let error = serde_json::from_str::<Debuginfo>(j).unwrap_err();
+    let expected_message = "invalid length 2, expected a map with a single key at line 1 column 29";
+    assert_eq!(error.to_string(), expected_message);
+
+    let map = InexactSizeHint(Some(("level", 2)), None);
+    let v = Debuginfo::deserialize(MapAccessDeserializer::new(map)).unwrap();
+    assert_eq!(v, Debuginfo::Level(2));
+}
+
+#[test]
+fn test_map_adjacently_tagged() {
+    #[derive(PartialEq, Debug)]
+    enum Message {
+        Ping,
+        Echo(String),
+        Wait(u64),
+        Quit,
+    }
+
+    impl<'de> Deserialize<'de> for Message {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .string(|string| match string {
+                    "ping" => Ok(Message::Ping),
+                    _ => Err(serde::de::Error::unknown_variant(string, &["ping"])),
+                })
+                .map_adjacently_tagged("t", "c", "echo", |content| {
+                    content.deserialize().map(Message::Echo)
+                })
+                .map_adjacently_tagged("t", "c", "wait", |content| {
+                    content.deserialize().map(Message::Wait)
+                })
+                .map_adjacently_tagged("t", "c", "quit", |content| {
+                    content.deserialize().map(|()| Message::Quit)
+                })
+                .deserialize(deserializer)
+        }
+    }
+
+    let j = r#" "ping" "#;
+    let v: Message = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Message::Ping);
+
+    let j = r#" {"t":"echo","c":"..."} "#;
+    let v: Message = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Message::Echo("...".to_owned()));
+
+    let j = r#" {"c":5,"t":"wait"} "#;
+    let v: Message = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Message::Wait(5));
+
+    let j = r#" {"t":"quit","id":1} "#;
+    let v: Message = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Message::Quit);
+
+    let j = r#" {"t":"wait","c":"..."} "#;
+    let error = serde_json::from_str::<Message>(j).unwrap_err();
+    let expected_message = "invalid type: string \"...\", expected u64 at line 1 column 22";
+    assert_eq!(error.to_string(), expected_message);
+
+    let j = r#" {"t":"exit"} "#;
+    let error = serde_json::from_str::<Message>(j).unwrap_err();
+    let expected_message = "invalid value: string \"exit\", expected `echo`, `wait` or `quit` as the value of `t` at line 1 column 13";
+    assert_eq!(error.to_string(), expected_message);
+}
+
+#[test]
+fn test_seq_len() {
+    #[derive(PartialEq, Debug)]
+    enum Point {
+        Planar(i32, i32),
+        Spatial(i32, i32, i32),
+    }
+
+    impl<'de> Deserialize<'de> for Point {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .seq_len(2, |seq| {
+                    let (x, y) = seq.deserialize()?;
+                    Ok(Point::Planar(x, y))
+                })
+                .seq_len(3, |seq| {
+                    let (x, y, z) = seq.deserialize()?;
+                    Ok(Point::Spatial(x, y, z))
+                })
+                .deserialize(deserializer)
+        }
+    }
+
+    // Like bincode: an element is only an integer if the caller asks for one,
+    // but the length of the sequence is known up front.
+    struct Compact(std::vec::IntoIter<i32>);
+
+    struct CompactInt(i32);
+
+    impl<'de> SeqAccess<'de> for Compact {
+        type Error = serde::de::value::Error;
+
+        fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+        where
+            T: serde::de::DeserializeSeed<'de>,
+        {
+            let element = self.0.next();
+            element.map(|v| seed.deserialize(CompactInt(v))).transpose()
+        }
+
+        fn size_hint(&self) -> Option<usize> {
+            Some(self.0.len())
+        }
+    }
+
+    impl<'de> Deserializer<'de> for CompactInt {
+        type Error = serde::de::value::Error;
+
+        fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            Err(serde::de::Error::custom("deserialize_any is not supported"))
+        }
+
+        fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            visitor.visit_i32(self.0)
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+            bytes byte_buf option unit unit_struct newtype_struct seq tuple
+            tuple_struct map struct enum identifier ignored_any
+        }
+    }
+
+    let j = "[1, 2]";
+    let v: Point = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Point::Planar(1, 2));
+
+    let j = "[1, 2, 3]";
+    let v: Point = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Point::Spatial(1, 2, 3));
+
+    let v = Point::deserialize(json!([1, 2, 3])).unwrap();
+    assert_eq!(v, Point::Spatial(1, 2, 3));
+
+    let seq = Compact(vec![1, 2].into_iter());
+    let v = Point::deserialize(SeqAccessDeserializer::new(seq)).unwrap();
+    assert_eq!(v, Point::Planar(1, 2));
+
+    let seq = Compact(vec![1, 2, 3, 4].into_iter());
+    let error = Point::deserialize(SeqAccessDeserializer::new(seq)).unwrap_err();
+    let expected_message = "invalid length 4, expected an array of length 2 or 3";
+    assert_eq!(error.to_string(), expected_message);
+
+    let j = "[1]";
+    let error = serde_json::from_str::<Point>(j).unwrap_err();
+    let expected_message =
+        "invalid length 1, expected an array of length 2 or 3 at line 1 column 3";
+    assert_eq!(error.to_string(), expected_message);
+}
+
+#[test]
+fn test_seq_by_first() {
+    #[derive(PartialEq, Debug)]
+    enum Step {
+        Command(Vec<String>),
+        Pipeline(Vec<Vec<String>>),
+        Nothing,
+    }
+
+    impl<'de> Deserialize<'de> for Step {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .seq_by_first(|first, seq| match first {
+                    Content::Seq(_) => seq.deserialize().map(Step::Pipeline),
+                    _ => seq.deserialize().map(Step::Command),
+                })
+                .seq(|seq| seq.deserialize().map(|[]: [(); 0]| Step::Nothing))
+                .deserialize(deserializer)
+        }
+    }
+
+    let j = r#" ["ls", "-l"] "#;
+    let v: Step = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Step::Command(vec!["ls".to_owned(), "-l".to_owned()]));
+
+    let j = r#" [["ls"], ["wc", "-l"]] "#;
+    let v: Step = serde_json::from_str(j).unwrap();
+    assert_eq!(
+        v,
+        Step::Pipeline(vec![
+            vec!["ls".to_owned()],
+            vec!["wc".to_owned(), "-l".to_owned()],
+        ]),
+    );
+
+    let j = "[]";
+    let v: Step = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Step::Nothing);
+}
+
+#[test]
+fn test_hint() {
+    #[derive(PartialEq, Debug)]
+    enum Version {
+        Number(u32),
+        Name(String),
+    }
+
+    impl<'de> Deserialize<'de> for Version {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let visitor = UntaggedEnumVisitor::new()
+                .u32(|n| Ok(Version::Number(n)))
+                .string(|s| Ok(Version::Name(s.to_owned())));
+            if deserializer.is_human_readable() {
+                visitor.deserialize(deserializer)
+            } else {
+                visitor.deserialize_with_hint(deserializer, Hint::Str)
+            }
+        }
+    }
+
+    #[derive(PartialEq, Debug)]
+    struct Unhinted(Version);
+
+    impl<'de> Deserialize<'de> for Unhinted {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .u32(|n| Ok(Unhinted(Version::Number(n))))
+                .string(|s| Ok(Unhinted(Version::Name(s.to_owned()))))
+                .deserialize(deserializer)
+        }
+    }
+
+    // Like bincode: a string is only a string if the caller asks for one.
+    struct Compact(&'static str);
+
+    impl<'de> Deserializer<'de> for Compact {
+        type Error = serde::de::value::Error;
+
+        fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            Err(serde::de::Error::custom("deserialize_any is not supported"))
+        }
+
+        fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            visitor.visit_borrowed_str(self.0)
+        }
+
+        fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            self.deserialize_str(visitor)
+        }
+
+        fn is_human_readable(&self) -> bool {
+            false
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char bytes
+            byte_buf option unit unit_struct newtype_struct seq tuple
+            tuple_struct map struct enum identifier ignored_any
+        }
+    }
+
+    // Like MessagePack: self-describing, but not human readable.
+    struct SelfDescribing(u32);
+
+    impl<'de> Deserializer<'de> for SelfDescribing {
+        type Error = serde::de::value::Error;
+
+        fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            visitor.visit_u32(self.0)
+        }
+
+        fn is_human_readable(&self) -> bool {
+            false
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str
+            string bytes byte_buf option unit unit_struct newtype_struct seq
+            tuple tuple_struct map struct enum identifier ignored_any
+        }
+    }
+
+    let v = Version::deserialize(Compact("stable")).unwrap();
+    assert_eq!(v, Version::Name("stable".to_owned()));
+
+    let error = Unhinted::deserialize(Compact("stable")).unwrap_err();
+    let expected_message = "deserialize_any is not supported";
+    assert_eq!(error.to_string(), expected_message);
+
+    let v = Unhinted::deserialize(SelfDescribing(2)).unwrap();
+    assert_eq!(v, Unhinted(Version::Number(2)));
+
+    let j = "2";
+    let v: Version = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Version::Number(2));
+
+    let j = r#" "stable" "#;
+    let v: Version = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Version::Name("stable".to_owned()));
+}
+
+#[test]
+fn test_hint_option() {
+    #[derive(PartialEq, Debug)]
+    struct Channel(Option<String>);
+
+    impl<'de> Deserialize<'de> for Channel {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .none(|| Ok(Channel(None)))
+                .some(|value| value.deserialize().map(|name| Channel(Some(name))))
+                .deserialize_with_hint(deserializer, Hint::Option)
+        }
+    }
+
+    #[derive(PartialEq, Debug)]
+    struct Transparent(Option<String>);
+
+    impl<'de> Deserialize<'de> for Transparent {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .none(|| Ok(Transparent(None)))
+                .string(|name| Ok(Transparent(Some(name.to_owned()))))
+                .deserialize_with_hint(deserializer, Hint::Option)
+        }
+    }
+
+    // Like bincode: an option is a tag followed by the content, and the
+    // content is only a string if the caller asks for one.
+    struct Compact(Option<&'static str>);
+
+    impl<'de> Deserializer<'de> for Compact {
+        type Error = serde::de::value::Error;
+
+        fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            Err(serde::de::Error::custom("deserialize_any is not supported"))
+        }
+
+        fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            match self.0 {
+                Some(_) => visitor.visit_some(self),
+                None => visitor.visit_none(),
+            }
+        }
+
+        fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            visitor.visit_borrowed_str(self.0.unwrap())
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str
+            bytes byte_buf unit unit_struct newtype_struct seq tuple
+            tuple_struct map struct enum identifier ignored_any
+        }
+    }
+
+    let v = Channel::deserialize(Compact(Some("beta"))).unwrap();
+    assert_eq!(v, Channel(Some("beta".to_owned())));
+
+    let v = Channel::deserialize(Compact(None)).unwrap();
+    assert_eq!(v, Channel(None));
+
+    // Without a `some` closure, the content of Some needs deserialize_any.
+    let v = Transparent::deserialize(Compact(None)).unwrap();
+    assert_eq!(v, Transparent(None));
+
+    let error = Transparent::deserialize(Compact(Some("beta"))).unwrap_err();
+    let expected_message = "deserialize_any is not supported";
+    assert_eq!(error.to_string(), expected_message);
+}
+
+#[test]
+fn test_human_readable_or_compact() {
+    #[derive(PartialEq, Debug)]
+    struct Address([u8; 4]);
+
+    impl<'de> Deserialize<'de> for Address {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .human_readable(|visitor| {
+                    visitor.string(|string| {
+                        let mut octets = [0; 4];
+                        let mut parts = string.split('.');
+                        for octet in &mut octets {
+                            let part = parts.next().unwrap_or_default();
+                            *octet = part.parse().map_err(serde::de::Error::custom)?;
+                        }
+                        Ok(Address(octets))
+                    })
+                })
+                .compact(|visitor| {
+                    visitor.bytes(|bytes| {
+                        let octets = bytes.try_into().map_err(serde::de::Error::custom)?;
+                        Ok(Address(octets))
+                    })
+                })
+                .deserialize(deserializer)
+        }
+    }
+
+    // Like CBOR: self-describing, but not human readable.
+    struct Binary(&'static [u8]);
+
+    impl<'de> Deserializer<'de> for Binary {
+        type Error = serde::de::value::Error;
+
+        fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            visitor.visit_borrowed_bytes(self.0)
+        }
+
+        fn is_human_readable(&self) -> bool {
+            false
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str
+            string bytes byte_buf option unit unit_struct newtype_struct seq
+            tuple tuple_struct map struct enum identifier ignored_any
+        }
+    }
+
+    let j = r#" "127.0.0.1" "#;
+    let v: Address = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Address([127, 0, 0, 1]));
+
+    let v = Address::deserialize(Binary(&[127, 0, 0, 1])).unwrap();
+    assert_eq!(v, Address([127, 0, 0, 1]));
+
+    let j = "[127, 0, 0, 1]";
+    let error = serde_json::from_str::<Address>(j).unwrap_err();
+    let expected_message = "invalid type: sequence, expected a string at line 1 column 1";
+    assert_eq!(error.to_string(), expected_message);
+}
+
+#[test]
+fn test_deserialize_struct() {
+    const NAME: &str = "$__magic_Spanned";
+    const FIELDS: &[&str] = &["start", "end", "value"];
+
+    #[derive(PartialEq, Debug)]
+    enum Setting {
+        Plain(String),
+        Spanned(usize, usize, String),
+    }
+
+    impl<'de> Deserialize<'de> for Setting {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .string(|string| Ok(Setting::Plain(string.to_owned())))
+                .map_when_key("start", |map| {
+                    let spanned: BTreeMap<&str, Content> = map.deserialize()?;
+                    let start = Deserialize::deserialize(spanned["start"].clone())?;
+                    let end = Deserialize::deserialize(spanned["end"].clone())?;
+                    let value = Deserialize::deserialize(spanned["value"].clone())?;
+                    Ok(Setting::Spanned(start, end, value))
+                })
+                .deserialize_struct(deserializer, NAME, FIELDS)
+        }
+    }
+
+    // Like toml: reports the location of a value if asked using a magic name.
+    struct Spans(&'static str);
+
+    impl<'de> Deserializer<'de> for Spans {
+        type Error = serde_untagged::de::Error;
+
+        fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            visitor.visit_borrowed_str(self.0)
+        }
+
+        fn deserialize_struct<V>(
+            self,
+            name: &'static str,
+            fields: &'static [&'static str],
+            visitor: V,
+        ) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            if name != NAME || fields != FIELDS {
+                return self.deserialize_any(visitor);
+            }
+            let spanned = Content::Map(vec![
+                (Content::Str("start"), Content::U64(1)),
+                (Content::Str("end"), Content::U64(1 + self.0.len() as u64)),
+                (Content::Str("value"), Content::Str(self.0)),
+            ]);
+            spanned.deserialize_any(visitor)
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str
+            string bytes byte_buf option unit unit_struct newtype_struct seq
+            tuple tuple_struct map enum identifier ignored_any
+        }
+    }
+
+    let v = Setting::deserialize(Spans("on")).unwrap();
+    assert_eq!(v, Setting::Spanned(1, 3, "on".to_owned()));
+
+    let j = r#" {"start":0,"end":2,"value":"on"} "#;
+    let v: Setting = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Setting::Spanned(0, 2, "on".to_owned()));
+}
+
+#[test]
+fn test_deserialize_newtype_struct() {
+    #[derive(PartialEq, Debug)]
+    struct Token(String);
+
+    impl<'de> Deserialize<'de> for Token {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .newtype_struct(|value| value.deserialize().map(Token))
+                .deserialize_newtype_struct(deserializer, "Token")
+        }
+    }
+
+    #[derive(PartialEq, Debug)]
+    struct Unwrapped(String);
+
+    impl<'de> Deserialize<'de> for Unwrapped {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .string(|string| Ok(Unwrapped(string.to_owned())))
+                .deserialize_newtype_struct(deserializer, "Unwrapped")
+        }
+    }
+
+    // Like bincode: a newtype struct is its content, and the content is only
+    // a string if the caller asks for one.
+    struct Compact(&'static str);
+
+    impl<'de> Deserializer<'de> for Compact {
+        type Error = serde::de::value::Error;
+
+        fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            Err(serde::de::Error::custom("deserialize_any is not supported"))
+        }
+
+        fn deserialize_newtype_struct<V>(
+            self,
+            _name: &'static str,
+            visitor: V,
+        ) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            visitor.visit_newtype_struct(self)
+        }
+
+        fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            visitor.visit_borrowed_str(self.0)
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str
+            bytes byte_buf option unit unit_struct seq tuple tuple_struct map
+            struct enum identifier ignored_any
+        }
+    }
+
+    let v = Token::deserialize(Compact("abc")).unwrap();
+    assert_eq!(v, Token("abc".to_owned()));
+
+    // Without a `newtype_struct` closure, the content needs deserialize_any.
+    let error = Unwrapped::deserialize(Compact("abc")).unwrap_err();
+    let expected_message = "deserialize_any is not supported";
+    assert_eq!(error.to_string(), expected_message);
+
+    let j = r#" "abc" "#;
+    let v: Unwrapped = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Unwrapped("abc".to_owned()));
+}
+
+#[test]
+fn test_parse_numeric_strings() {
+    #[derive(PartialEq, Debug)]
+    enum Setting {
+        Int(i64),
+        Wide(i128),
+        Float(f64),
+        Text(String),
+    }
+
+    impl<'de> Deserialize<'de> for Setting {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .i64(|int| Ok(Setting::Int(int)))
+                .i128(|int| Ok(Setting::Wide(int)))
+                .f64(|float| Ok(Setting::Float(float)))
+                .string(|string| Ok(Setting::Text(string.to_owned())))
+                .parse_numeric_strings()
+                .deserialize(deserializer)
+        }
+    }
+
+    let cases = [
+        (r#" "42" "#, Setting::Int(42)),
+        (r#" "-7" "#, Setting::Int(-7)),
+        (r#" "0x1F" "#, Setting::Int(31)),
+        (r#" "-0b101" "#, Setting::Int(-5)),
+        (r#" "1_000" "#, Setting::Int(1000)),
+        (
+            r#" "170141183460469231731687303715884105727" "#,
+            Setting::Wide(i128::MAX),
+        ),
+        (
+            r#" "-170141183460469231731687303715884105728" "#,
+            Setting::Wide(i128::MIN),
+        ),
+        (r#" "3.5" "#, Setting::Float(3.5)),
+        (r#" "1e3" "#, Setting::Float(1000.0)),
+        (r#" "inf" "#, Setting::Text("inf".to_owned())),
+        (r#" "1__0" "#, Setting::Text("1__0".to_owned())),
+        (r#" "0x" "#, Setting::Text("0x".to_owned())),
+        (r#" "--1" "#, Setting::Text("--1".to_owned())),
+        (r#" "12ab" "#, Setting::Text("12ab".to_owned())),
+        (r#" "0x1_f" "#, Setting::Int(31)),
+        (r#" "1.5_e3" "#, Setting::Text("1.5_e3".to_owned())),
+        (r#" "1_e3" "#, Setting::Text("1_e3".to_owned())),
+        (r#" "1e_3" "#, Setting::Text("1e_3".to_owned())),
+        (r#" "1_f" "#, Setting::Text("1_f".to_owned())),
+        (r#" "0b1_a" "#, Setting::Text("0b1_a".to_owned())),
+        (r#" "0b1_2" "#, Setting::Text("0b1_2".to_owned())),
+        (r#" "0o7_8" "#, Setting::Text("0o7_8".to_owned())),
+        ("42", Setting::Int(42)),
+    ];
+
+    for (j, expected) in cases {
+        let v: Setting = serde_json::from_str(j).unwrap();
+        assert_eq!(v, expected, "{j}");
+    }
+}
+
+#[test]
+fn test_parse_numeric_strings_before_bytes() {
+    #[derive(PartialEq, Debug)]
+    enum Id {
+        Number(u32),
+        Bytes(Vec<u8>),
+    }
+
+    impl<'de> Deserialize<'de> for Id {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .u32(|number| Ok(Id::Number(number)))
+                .byte_buf(|bytes| Ok(Id::Bytes(bytes)))
+                .bytes_coercion(BytesCoercion::STR)
+                .parse_numeric_strings()
+                .deserialize(deserializer)
+        }
+    }
+
+    // Borrowed and escaped strings are coerced in the same order.
+    let j = r#" "5" "#;
+    let v: Id = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Id::Number(5));
+
+    let j = r#" "\u0035" "#;
+    let v: Id = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Id::Number(5));
+
+    let j = r#" "x5" "#;
+    let v: Id = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Id::Bytes(b"x5".to_vec()));
+}
+
+#[test]
+fn test_bool_coercion() {
+    #[derive(PartialEq, Debug)]
+    enum Color {
+        Auto(bool),
+        Theme(String),
+    }
+
+    impl<'de> Deserialize<'de> for Color {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .bool(|auto| Ok(Color::Auto(auto)))
+                .string(|theme| Ok(Color::Theme(theme.to_owned())))
+                .bool_coercion(BoolCoercion::YES_NO | BoolCoercion::ON_OFF)
+                .deserialize(deserializer)
+        }
+    }
+
+    #[derive(PartialEq, Debug)]
+    enum Verbose {
+        Flag(bool),
+        Module(String),
+    }
+
+    impl<'de> Deserialize<'de> for Verbose {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .bool(|flag| Ok(Verbose::Flag(flag)))
+                .string(|module| Ok(Verbose::Module(module.to_owned())))
+                .bool_coercion(BoolCoercion::ONE_ZERO)
+                .deserialize(deserializer)
+        }
+    }
+
+    // Strings match case-insensitively, and only the enabled spellings.
+    let j = r#" "Yes" "#;
+    let v: Color = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Color::Auto(true));
+
+    let j = r#" "OFF" "#;
+    let v: Color = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Color::Auto(false));
+
+    let j = r#" "true" "#;
+    let v: Color = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Color::Theme("true".to_owned()));
+
+    let j = r#" "yess" "#;
+    let v: Color = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Color::Theme("yess".to_owned()));
+
+    // The strings "1" and "0" are a separate case from the integers 1 and 0.
+    let j = r#" "0" "#;
+    let v: Verbose = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Verbose::Flag(false));
+
+    let j = "1";
+    let error = serde_json::from_str::<Verbose>(j).unwrap_err();
+    let expected_message =
+        "invalid type: integer `1`, expected a boolean or string at line 1 column 1";
+    assert_eq!(error.to_string(), expected_message);
+}
+
+#[test]
+fn test_bool_coercion_integer() {
+    #[derive(PartialEq, Debug)]
+    enum Cache {
+        Enabled(bool),
+        Path(String),
+    }
+
+    impl<'de> Deserialize<'de> for Cache {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .bool(|enabled| Ok(Cache::Enabled(enabled)))
+                .string(|path| Ok(Cache::Path(path.to_owned())))
+                .bool_coercion(BoolCoercion::INTEGER)
+                .deserialize(deserializer)
+        }
+    }
+
+    #[derive(PartialEq, Debug)]
+    enum Retry {
+        Enabled(bool),
+        Attempts(u8),
+    }
+
+    impl<'de> Deserialize<'de> for Retry {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .bool(|enabled| Ok(Retry::Enabled(enabled)))
+                .u8(|attempts| Ok(Retry::Attempts(attempts)))
+                .bool_coercion(BoolCoercion::INTEGER)
+                .deserialize(deserializer)
+        }
+    }
+
+    let j = "1";
+    let v: Cache = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Cache::Enabled(true));
+
+    let j = r#" "1" "#;
+    let v: Cache = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Cache::Path("1".to_owned()));
+
+    let j = "2";
+    let error = serde_json::from_str::<Cache>(j).unwrap_err();
+    let expected_message =
+        "invalid type: integer `2`, expected a boolean or string at line 1 column 1";
+    assert_eq!(error.to_string(), expected_message);
+
+    // An integer closure takes precedence over coercing integers to bool.
+    let j = "1";
+    let v: Retry = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Retry::Attempts(1));
+
+    let j = "false";
+    let v: Retry = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Retry::Enabled(false));
+}
+
+#[test]
+fn test_int_to_float() {
+    #[derive(PartialEq, Debug)]
+    struct Ratio(f64);
+
+    impl<'de> Deserialize<'de> for Ratio {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .f64(|seconds| Ok(Ratio(seconds)))
+                .deserialize(deserializer)
+        }
+    }
+
+    #[derive(PartialEq, Debug)]
+    struct Seconds(f64);
+
+    impl<'de> Deserialize<'de> for Seconds {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .f64(|seconds| Ok(Seconds(seconds)))
+                .int_to_float(FloatPrecision::Exact)
+                .deserialize(deserializer)
+        }
+    }
+
+    #[derive(PartialEq, Debug)]
+    struct RoundedSeconds(f64);
+
+    impl<'de> Deserialize<'de> for RoundedSeconds {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .f64(|seconds| Ok(RoundedSeconds(seconds)))
+                .int_to_float(FloatPrecision::Round)
+                .deserialize(deserializer)
+        }
+    }
+
+    #[derive(PartialEq, Debug)]
+    struct LossySeconds(f64);
+
+    impl<'de> Deserialize<'de> for LossySeconds {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .f64(|seconds| Ok(LossySeconds(seconds)))
+                .int_to_float(FloatPrecision::Lossy)
+                .deserialize(deserializer)
+        }
+    }
+
+    let j = "1";
+    let error = serde_json::from_str::<Ratio>(j).unwrap_err();
+    let expected_message = "invalid type: integer `1`, expected a float at line 1 column 1";
+    assert_eq!(error.to_string(), expected_message);
+
+    let j = "-30";
+    let v: Seconds = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Seconds(-30.0));
+
+    // 2^60 has only one significant bit.
+    let j = "1152921504606846976";
+    let v: Seconds = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Seconds(1_152_921_504_606_846_976.0));
+
+    // 2^53 + 1 is the smallest integer that f64 cannot represent.
+    let j = "9007199254740993";
+    let error = serde_json::from_str::<Seconds>(j).unwrap_err();
+    let expected_message = "invalid value: integer `9007199254740993`, expected an integer exactly representable as f64 at line 1 column 16";
+    assert_eq!(error.to_string(), expected_message);
+
+    let j = "9007199254740993";
+    let v: RoundedSeconds = serde_json::from_str(j).unwrap();
+    assert_eq!(v, RoundedSeconds(9_007_199_254_740_992.0));
+
+    // Every integer is within the range of f64, so Lossy is the same as Round.
+    let j = "9007199254740993";
+    let v: LossySeconds = serde_json::from_str(j).unwrap();
+    assert_eq!(v, LossySeconds(9_007_199_254_740_992.0));
+
+    let deserializer = U128Deserializer::<serde::de::value::Error>::new(u128::MAX);
+    let v = RoundedSeconds::deserialize(deserializer).unwrap();
+    assert_eq!(
+        v,
+        RoundedSeconds(340_282_366_920_938_463_463_374_607_431_768_211_455.0)
+    );
+
+    let deserializer = U128Deserializer::<serde::de::value::Error>::new(u128::MAX);
+    let v = LossySeconds::deserialize(deserializer).unwrap();
+    assert_eq!(
+        v,
+        LossySeconds(340_282_366_920_938_463_463_374_607_431_768_211_455.0)
+    );
+}
+
+#[test]
+fn test_int_to_f32() {
+    #[derive(PartialEq, Debug)]
+    struct Scale(f32);
+
+    impl<'de> Deserialize<'de> for Scale {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .f32(|scale| Ok(Scale(scale)))
+                .int_to_float(FloatPrecision::Exact)
+                .deserialize(deserializer)
+        }
+    }
+
+    #[derive(PartialEq, Debug)]
+    struct RoundedScale(f32);
+
+    impl<'de> Deserialize<'de> for RoundedScale {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .f32(|scale| Ok(RoundedScale(scale)))
+                .int_to_float(FloatPrecision::Round)
+                .deserialize(deserializer)
+        }
+    }
+
+    #[derive(PartialEq, Debug)]
+    struct LossyScale(f32);
+
+    impl<'de> Deserialize<'de> for LossyScale {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .f32(|scale| Ok(LossyScale(scale)))
+                .int_to_float(FloatPrecision::Lossy)
+                .deserialize(deserializer)
+        }
+    }
+
+    let j = "16777216";
+    let v: Scale = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Scale(16_777_216.0));
+
+    let j = "16777217";
+    let error = serde_json::from_str::<Scale>(j).unwrap_err();
+    let expected_message = "invalid value: integer `16777217`, expected an integer exactly representable as f32 at line 1 column 8";
+    assert_eq!(error.to_string(), expected_message);
+
+    let j = "16777217";
+    let v: RoundedScale = serde_json::from_str(j).unwrap();
+    assert_eq!(v, RoundedScale(16_777_216.0));
+
+    let deserializer = I128Deserializer::<serde::de::value::Error>::new(i128::MIN);
+    let v = Scale::deserialize(deserializer).unwrap();
+    assert_eq!(
+        v,
+        Scale(-170_141_183_460_469_231_731_687_303_715_884_105_728.0)
+    );
+
+    // Overflow to infinity is a range error, not lost precision, and is an
+    // error even when rounding.
+    let deserializer = U128Deserializer::<serde::de::value::Error>::new(u128::MAX);
+    let error = Scale::deserialize(deserializer).unwrap_err();
+    let expected_message = "invalid value: integer `340282366920938463463374607431768211455`, expected an integer within the range of f32";
+    assert_eq!(error.to_string(), expected_message);
+
+    let deserializer = U128Deserializer::<serde::de::value::Error>::new(u128::MAX);
+    let error = RoundedScale::deserialize(deserializer).unwrap_err();
+    let expected_message = "invalid value: integer `340282366920938463463374607431768211455`, expected an integer within the range of f32";
+    assert_eq!(error.to_string(), expected_message);
+
+    let deserializer = U128Deserializer::<serde::de::value::Error>::new(u128::MAX);
+    let v = LossyScale::deserialize(deserializer).unwrap();
+    assert_eq!(v, LossyScale(f32::INFINITY));
+}
+
+#[test]
+fn test_float_to_int() {
+    #[derive(PartialEq, Debug)]
+    enum Retries {
+        Count(u32),
+        Unlimited(bool),
+    }
+
+    impl<'de> Deserialize<'de> for Retries {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .u32(|count| Ok(Retries::Count(count)))
+                .bool(|unlimited| Ok(Retries::Unlimited(unlimited)))
+                .float_to_int()
+                .deserialize(deserializer)
+        }
+    }
+
+    #[derive(PartialEq, Debug)]
+    enum Ratio {
+        Whole(u32),
+        Fraction(f32),
+    }
+
+    impl<'de> Deserialize<'de> for Ratio {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .u32(|whole| Ok(Ratio::Whole(whole)))
+                .f32(|fraction| Ok(Ratio::Fraction(fraction)))
+                .float_to_int()
+                .deserialize(deserializer)
+        }
+    }
+
+    let j = "3.0";
+    let v: Retries = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Retries::Count(3));
+
+    let j = "3";
+    let v: Retries = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Retries::Count(3));
+
+    let j = "3.5";
+    let error = serde_json::from_str::<Retries>(j).unwrap_err();
+    let expected_message = "invalid value: floating point `3.5`, expected an integer; the fractional part 0.5 would be lost at line 1 column 3";
+    assert_eq!(error.to_string(), expected_message);
+
+    let j = "-2.25";
+    let error = serde_json::from_str::<Retries>(j).unwrap_err();
+    let expected_message = "invalid value: floating point `-2.25`, expected an integer; the fractional part 0.25 would be lost at line 1 column 5";
+    assert_eq!(error.to_string(), expected_message);
+
+    let j = "1e40";
+    let error = serde_json::from_str::<Retries>(j).unwrap_err();
+    let expected_message = "invalid value: floating point `1e+40`, expected an integer within the range of i128 or u128 at line 1 column 4";
+    assert_eq!(error.to_string(), expected_message);
+
+    let j = "-1.0";
+    let error = serde_json::from_str::<Retries>(j).unwrap_err();
+    let expected_message = "invalid value: floating point `-1.0`, expected an integer between 0 and 4294967295 at line 1 column 4";
+    assert_eq!(error.to_string(), expected_message);
+
+    let j = "5e9";
+    let error = serde_json::from_str::<Retries>(j).unwrap_err();
+    let expected_message = "invalid value: floating point `5000000000.0`, expected an integer between 0 and 4294967295 at line 1 column 3";
+    assert_eq!(error.to_string(), expected_message);
+
+    // JSON floats are f64, which the f32 closure does not receive.
+    let j = "2.0";
+    let v: Ratio = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Ratio::Whole(2));
+
+    let j = "0.5";
+    let error = serde_json::from_str::<Ratio>(j).unwrap_err();
+    let expected_message = "invalid value: floating point `0.5`, expected an integer; the fractional part 0.5 would be lost at line 1 column 3";
+    assert_eq!(error.to_string(), expected_message);
+
+    let deserializer = F32Deserializer::<serde::de::value::Error>::new(0.5);
+    let v = Ratio::deserialize(deserializer).unwrap();
+    assert_eq!(v, Ratio::Fraction(0.5));
+}
+
+#[test]
+fn test_integer_and_number() {
+    #[derive(PartialEq, Debug)]
+    enum Limit {
+        Small(u8),
+        Bytes(Integer),
+        Name(String),
+    }
+
+    impl<'de> Deserialize<'de> for Limit {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .u8(|int| Ok(Limit::Small(int)))
+                .integer(|int| Ok(Limit::Bytes(int)))
+                .string(|string| Ok(Limit::Name(string.to_owned())))
+                .deserialize(deserializer)
+        }
+    }
+
+    let j = "1";
+    let v: Limit = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Limit::Small(1));
+
+    let j = "1000";
+    let v: Limit = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Limit::Bytes(Integer::U64(1000)));
+
+    let j = "-1";
+    let v: Limit = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Limit::Bytes(Integer::I64(-1)));
+
+    let j = "1.5";
+    let error = serde_json::from_str::<Limit>(j).unwrap_err();
+    let expected_message =
+        "invalid type: floating point `1.5`, expected an integer or string at line 1 column 3";
+    assert_eq!(error.to_string(), expected_message);
+
+    assert_eq!(Integer::I64(-1).as_u64(), None);
+    assert_eq!(Integer::I64(-1).as_i8(), Some(-1));
+    assert_eq!(Integer::U128(u128::MAX).as_u128(), Some(u128::MAX));
+    assert_eq!(Integer::U128(u128::MAX).as_i128(), None);
+
+    let number = UntaggedEnumVisitor::new()
+        .number(Ok)
+        .deserialize(&json!(2.5))
+        .unwrap();
+    assert_eq!(number, Number::F64(2.5));
+    assert_eq!(number.as_i64(), None);
+
+    let number = UntaggedEnumVisitor::new()
+        .number(Ok)
+        .deserialize(&json!(7))
+        .unwrap();
+    assert_eq!(number, Number::Integer(Integer::U64(7)));
+    assert_eq!(number.as_i32(), Some(7));
+    assert_eq!(number.as_f64_lossy().to_bits(), 7.0f64.to_bits());
+
+    let error = UntaggedEnumVisitor::<Number>::new()
+        .number(Ok)
+        .deserialize(&json!("7"))
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "invalid type: string \"7\", expected a number"
+    );
+}
+
+#[test]
+fn test_integer_precedence() {
+    #[derive(PartialEq, Debug)]
+    enum Int {
+        I8(i8),
+        I64(i64),
+        U8(u8),
+        U64(u64),
+    }
+
+    #[derive(PartialEq, Debug)]
+    struct Input(Int);
+
+    impl<'de> Deserialize<'de> for Input {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .i8(|int| Ok(Input(Int::I8(int))))
+                .i64(|int| Ok(Input(Int::I64(int))))
+                .u8(|int| Ok(Input(Int::U8(int))))
+                .u64(|int| Ok(Input(Int::U64(int))))
+                .integer_precedence(Precedence::Input)
+                .deserialize(deserializer)
+        }
+    }
+
+    #[derive(PartialEq, Debug)]
+    struct Narrowest(Int);
+
+    impl<'de> Deserialize<'de> for Narrowest {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .i8(|int| Ok(Narrowest(Int::I8(int))))
+                .i64(|int| Ok(Narrowest(Int::I64(int))))
+                .u8(|int| Ok(Narrowest(Int::U8(int))))
+                .u64(|int| Ok(Narrowest(Int::U64(int))))
+                .integer_precedence(Precedence::Narrowest)
+                .deserialize(deserializer)
+        }
+    }
+
+    #[derive(PartialEq, Debug)]
+    struct Widest(Int);
+
+    impl<'de> Deserialize<'de> for Widest {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .i8(|int| Ok(Widest(Int::I8(int))))
+                .i64(|int| Ok(Widest(Int::I64(int))))
+                .u8(|int| Ok(Widest(Int::U8(int))))
+                .u64(|int| Ok(Widest(Int::U64(int))))
+                .integer_precedence(Precedence::Widest)
+                .deserialize(deserializer)
+        }
+    }
+
+    // serde_json produces u64 for non-negative integers and i64 otherwise.
+    let j = "5";
+    let v: Input = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Input(Int::U64(5)));
+
+    let j = "-5";
+    let v: Input = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Input(Int::I64(-5)));
+
+    // Signed wins a tie in width.
+    let j = "5";
+    let v: Narrowest = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Narrowest(Int::I8(5)));
+
+    let j = "200";
+    let v: Narrowest = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Narrowest(Int::U8(200)));
+
+    let j = "5";
+    let v: Widest = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Widest(Int::I64(5)));
+
+    let j = "18446744073709551615";
+    let v: Widest = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Widest(Int::U64(u64::MAX)));
+}
+
+#[test]
+fn test_integer_precedence_signedness() {
+    #[derive(PartialEq, Debug)]
+    enum Int {
+        I8(i8),
+        I64(i64),
+        U8(u8),
+        U64(u64),
+    }
+
+    #[derive(PartialEq, Debug)]
+    struct Signed(Int);
+
+    impl<'de> Deserialize<'de> for Signed {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .i8(|int| Ok(Signed(Int::I8(int))))
+                .i64(|int| Ok(Signed(Int::I64(int))))
+                .u8(|int| Ok(Signed(Int::U8(int))))
+                .u64(|int| Ok(Signed(Int::U64(int))))
+                .integer_precedence(Precedence::Signed)
+                .deserialize(deserializer)
+        }
+    }
+
+    #[derive(PartialEq, Debug)]
+    struct Unsigned(Int);
+
+    impl<'de> Deserialize<'de> for Unsigned {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .i8(|int| Ok(Unsigned(Int::I8(int))))
+                .i64(|int| Ok(Unsigned(Int::I64(int))))
+                .u8(|int| Ok(Unsigned(Int::U8(int))))
+                .u64(|int| Ok(Unsigned(Int::U64(int))))
+                .integer_precedence(Precedence::Unsigned)
+                .deserialize(deserializer)
+        }
+    }
+
+    // Every type of the preferred signedness is tried before any of the other.
+    let j = "200";
+    let v: Signed = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Signed(Int::I64(200)));
+
+    let j = "5";
+    let v: Signed = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Signed(Int::I8(5)));
+
+    let j = "5";
+    let v: Unsigned = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Unsigned(Int::U8(5)));
+
+    let j = "-5";
+    let v: Unsigned = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Unsigned(Int::I8(-5)));
+}
+
+#[test]
+fn test_integer_precedence_explicit() {
+    #[derive(PartialEq, Debug)]
+    enum Int {
+        I8(i8),
+        I64(i64),
+        U8(u8),
+        U64(u64),
+    }
+
+    #[derive(PartialEq, Debug)]
+    struct U8ThenI64(Int);
+
+    impl<'de> Deserialize<'de> for U8ThenI64 {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .i8(|int| Ok(U8ThenI64(Int::I8(int))))
+                .i64(|int| Ok(U8ThenI64(Int::I64(int))))
+                .u8(|int| Ok(U8ThenI64(Int::U8(int))))
+                .u64(|int| Ok(U8ThenI64(Int::U64(int))))
+                .integer_precedence(Precedence::Explicit(&[IntKind::U8, IntKind::I64]))
+                .deserialize(deserializer)
+        }
+    }
+
+    #[derive(PartialEq, Debug)]
+    struct OnlyI8(Int);
+
+    impl<'de> Deserialize<'de> for OnlyI8 {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .i8(|int| Ok(OnlyI8(Int::I8(int))))
+                .i64(|int| Ok(OnlyI8(Int::I64(int))))
+                .u8(|int| Ok(OnlyI8(Int::U8(int))))
+                .u64(|int| Ok(OnlyI8(Int::U64(int))))
+                .integer_precedence(Precedence::Explicit(&[IntKind::I8]))
+                .deserialize(deserializer)
+        }
+    }
+
+    #[derive(PartialEq, Debug)]
+    enum Port {
+        Number(u16),
+        Name(String),
+    }
+
+    impl<'de> Deserialize<'de> for Port {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .u16(|port| Ok(Port::Number(port)))
+                .string(|name| Ok(Port::Name(name.to_owned())))
+                .integer_precedence(Precedence::Explicit(&[IntKind::U8]))
+                .parse_numeric_strings()
+                .float_to_int()
+                .deserialize(deserializer)
+        }
+    }
+
+    let j = "5";
+    let v: U8ThenI64 = serde_json::from_str(j).unwrap();
+    assert_eq!(v, U8ThenI64(Int::U8(5)));
+
+    let j = "300";
+    let v: U8ThenI64 = serde_json::from_str(j).unwrap();
+    assert_eq!(v, U8ThenI64(Int::I64(300)));
+
+    // Closures for types not in the explicit list are not used.
+    let j = "300";
+    let error = serde_json::from_str::<OnlyI8>(j).unwrap_err();
+    let expected_message =
+        "invalid value: integer `300`, expected an integer between -128 and 127 at line 1 column 3";
+    assert_eq!(error.to_string(), expected_message);
+
+    // The explicit list also limits which handlers numeric strings and
+    // integral floats are checked against.
+    let j = r#" "5" "#;
+    let v: Port = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Port::Name("5".to_owned()));
+
+    // None of the closures that the list allows accept integers, so neither
+    // does the error message.
+    let j = "5.0";
+    let error = serde_json::from_str::<Port>(j).unwrap_err();
+    let expected_message =
+        "invalid type: floating point `5.0`, expected a string at line 1 column 3";
+    assert_eq!(error.to_string(), expected_message);
+
+    let j = "5";
+    let error = serde_json::from_str::<Port>(j).unwrap_err();
+    let expected_message = "invalid type: integer `5`, expected a string at line 1 column 1";
+    assert_eq!(error.to_string(), expected_message);
+}
+
+#[test]
+fn test_integer_range() {
+    let error = UntaggedEnumVisitor::new()
+        .u8(|_| Ok(()))
+        .deserialize(&json!(300))
+        .unwrap_err();
+    let expected_message = "invalid value: integer `300`, expected an integer between 0 and 255";
+    assert_eq!(error.to_string(), expected_message);
+
+    let error = UntaggedEnumVisitor::new()
+        .u8(|_| Ok(()))
+        .i16(|_| Ok(()))
+        .deserialize(&json!(-40_000))
+        .unwrap_err();
+    let expected_message =
+        "invalid value: integer `-40000`, expected an integer between -32768 and 32767";
+    assert_eq!(error.to_string(), expected_message);
+
+    let deserializer = U128Deserializer::<serde::de::value::Error>::new(u128::MAX);
+    let error = UntaggedEnumVisitor::new()
+        .u64(|_| Ok(()))
+        .deserialize(deserializer)
+        .unwrap_err();
+    let expected_message = "invalid value: integer `340282366920938463463374607431768211455`, expected an integer between 0 and 18446744073709551615";
+    assert_eq!(error.to_string(), expected_message);
+
+    let deserializer = I128Deserializer::<serde::de::value::Error>::new(i128::MIN);
+    let error = UntaggedEnumVisitor::new()
+        .i64(|_| Ok(()))
+        .u128(|_| Ok(()))
+        .deserialize(deserializer)
+        .unwrap_err();
+    let expected_message = "invalid value: integer `-170141183460469231731687303715884105728`, expected an integer between -9223372036854775808 and 340282366920938463463374607431768211455";
+    assert_eq!(error.to_string(), expected_message);
+
+    let error = UntaggedEnumVisitor::new()
+        .string(|_| Ok(()))
+        .deserialize(&json!(300))
+        .unwrap_err();
+    let expected_message = "invalid type: integer `300`, expected a string";
+    assert_eq!(error.to_string(), expected_message);
+}
+
+#[test]
+fn test_char_from_string() {
+    #[derive(PartialEq, Debug)]
+    enum Separator {
+        Char(char),
+        Byte(u8),
+    }
+
+    impl<'de> Deserialize<'de> for Separator {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .char(|ch| Ok(Separator::Char(ch)))
+                .u8(|byte| Ok(Separator::Byte(byte)))
+                .deserialize(deserializer)
+        }
+    }
+
+    #[derive(PartialEq, Debug)]
+    struct StrictSeparator(char);
+
+    impl<'de> Deserialize<'de> for StrictSeparator {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .char(|ch| Ok(StrictSeparator(ch)))
+                .strict_char_and_string()
+                .deserialize(deserializer)
+        }
+    }
+
+    #[derive(PartialEq, Debug)]
+    struct Name(String);
+
+    impl<'de> Deserialize<'de> for Name {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .string(|string| Ok(Name(string.to_owned())))
+                .deserialize(deserializer)
+        }
+    }
+
+    #[derive(PartialEq, Debug)]
+    struct StrictName(String);
+
+    impl<'de> Deserialize<'de> for StrictName {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .string(|string| Ok(StrictName(string.to_owned())))
+                .strict_char_and_string()
+                .deserialize(deserializer)
+        }
+    }
+
+    let j = r#" "," "#;
+    let v: Separator = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Separator::Char(','));
+
+    let j = r#" "é" "#;
+    let v: Separator = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Separator::Char('é'));
+
+    // Length is counted in characters, not bytes or graphemes.
+    let j = r#" "e\u0301" "#;
+    let error = serde_json::from_str::<Separator>(j).unwrap_err();
+    let expected_message = "invalid length 2, expected a single character at line 1 column 10";
+    assert_eq!(error.to_string(), expected_message);
+
+    let j = r#" "" "#;
+    let error = serde_json::from_str::<Separator>(j).unwrap_err();
+    let expected_message = "invalid length 0, expected a single character at line 1 column 3";
+    assert_eq!(error.to_string(), expected_message);
+
+    let j = r#" "," "#;
+    let error = serde_json::from_str::<StrictSeparator>(j).unwrap_err();
+    let expected_message = "invalid type: string \",\", expected a character at line 1 column 4";
+    assert_eq!(error.to_string(), expected_message);
+
+    let deserializer = CharDeserializer::<serde::de::value::Error>::new('x');
+    let v = Name::deserialize(deserializer).unwrap();
+    assert_eq!(v, Name("x".to_owned()));
+
+    let deserializer = CharDeserializer::<serde::de::value::Error>::new('x');
+    let error = StrictName::deserialize(deserializer).unwrap_err();
+    let expected_message = "invalid type: character `x`, expected a string";
+    assert_eq!(error.to_string(), expected_message);
+}
+
+#[test]
+fn test_bytes_coercion() {
+    #[derive(PartialEq, Debug)]
+    struct Hex(Vec<u8>);
+
+    impl<'de> Deserialize<'de> for Hex {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .byte_buf(|bytes| Ok(Hex(bytes)))
+                .bytes_coercion(BytesCoercion::HEX)
+                .deserialize(deserializer)
+        }
+    }
+
+    #[derive(PartialEq, Debug)]
+    struct HexOrBase64(Vec<u8>);
+
+    impl<'de> Deserialize<'de> for HexOrBase64 {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .byte_buf(|bytes| Ok(HexOrBase64(bytes)))
+                .bytes_coercion(BytesCoercion::HEX | BytesCoercion::BASE64)
+                .deserialize(deserializer)
+        }
+    }
+
+    #[derive(PartialEq, Debug)]
+    struct Octets(Vec<u8>);
+
+    impl<'de> Deserialize<'de> for Octets {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .byte_buf(|bytes| Ok(Octets(bytes)))
+                .bytes_coercion(BytesCoercion::SEQ)
+                .deserialize(deserializer)
+        }
+    }
+
+    #[derive(PartialEq, Debug)]
+    struct Raw(Vec<u8>);
+
+    impl<'de> Deserialize<'de> for Raw {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .byte_buf(|bytes| Ok(Raw(bytes)))
+                .bytes_coercion(BytesCoercion::NONE)
+                .deserialize(deserializer)
+        }
+    }
+
+    let j = r#" "C0ffee" "#;
+    let v: Hex = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Hex(vec![0xc0, 0xff, 0xee]));
+
+    let j = r#" "c0ffe" "#;
+    let error = serde_json::from_str::<Hex>(j).unwrap_err();
+    let expected_message =
+        "invalid value: string \"c0ffe\", expected a hex string at line 1 column 8";
+    assert_eq!(error.to_string(), expected_message);
+
+    // Hex takes precedence over base64 for strings that are valid as both.
+    let j = r#" "beef" "#;
+    let v: HexOrBase64 = serde_json::from_str(j).unwrap();
+    assert_eq!(v, HexOrBase64(vec![0xbe, 0xef]));
+
+    let j = r#" "aGVsbG8=" "#;
+    let v: HexOrBase64 = serde_json::from_str(j).unwrap();
+    assert_eq!(v, HexOrBase64(b"hello".to_vec()));
+
+    // URL-safe alphabet, without padding.
+    let j = r#" "-_8" "#;
+    let v: HexOrBase64 = serde_json::from_str(j).unwrap();
+    assert_eq!(v, HexOrBase64(vec![0xfb, 0xff]));
+
+    let j = "[1, 2, 255]";
+    let v: Octets = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Octets(vec![1, 2, 255]));
+
+    let j = "[1, 2, 256]";
+    let error = serde_json::from_str::<Octets>(j).unwrap_err();
+    let expected_message = "invalid value: integer `256`, expected u8 at line 1 column 10";
+    assert_eq!(error.to_string(), expected_message);
+
+    let j = r#" "c0ffee" "#;
+    let error = serde_json::from_str::<Raw>(j).unwrap_err();
+    let expected_message =
+        "invalid type: string \"c0ffee\", expected a byte array at line 1 column 9";
+    assert_eq!(error.to_string(), expected_message);
+}
+
+#[test]
+fn test_bytes_from_str() {
+    #[derive(PartialEq, Debug)]
+    enum Key<'a> {
+        Borrowed(&'a [u8]),
+        Owned(Vec<u8>),
+    }
+
+    impl<'de> Deserialize<'de> for Key<'de> {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .borrowed_bytes(|key| Ok(Key::Borrowed(key)))
+                .byte_buf(|key| Ok(Key::Owned(key)))
+                .bytes_coercion(BytesCoercion::STR)
+                .deserialize(deserializer)
+        }
+    }
+
+    // A string is only borrowed if the input contains no escapes.
+    let j = r#" "hello" "#;
+    let v: Key = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Key::Borrowed(b"hello"));
+
+    let j = r#" "a\nb" "#;
+    let v: Key = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Key::Owned(b"a\nb".to_vec()));
+}
+
+#[test]
+fn test_bytes_as_str() {
+    #[derive(PartialEq, Debug)]
+    enum Name<'a> {
+        Borrowed(&'a str),
+        Owned(String),
+        Bytes(Vec<u8>),
+    }
+
+    let deserializer = BorrowedBytesDeserializer::<serde::de::value::Error>::new(b"alice");
+    let v = UntaggedEnumVisitor::new()
+        .borrowed_str(|name| Ok(Name::Borrowed(name)))
+        .bytes_as_str()
+        .deserialize(deserializer)
+        .unwrap();
+    assert_eq!(v, Name::Borrowed("alice"));
+
+    let deserializer = BytesDeserializer::<serde::de::value::Error>::new(b"alice");
+    let v = UntaggedEnumVisitor::new()
+        .string(|name| Ok(Name::Owned(name.to_owned())))
+        .bytes_as_str()
+        .deserialize(deserializer)
+        .unwrap();
+    assert_eq!(v, Name::Owned("alice".to_owned()));
+
+    // The offset is where the first invalid sequence starts, which for a
+    // truncated multi-byte character is the start of the character.
+    let deserializer = BytesDeserializer::<serde::de::value::Error>::new(b"caf\xc3");
+    let error = UntaggedEnumVisitor::new()
+        .string(|name| Ok(Name::Owned(name.to_owned())))
+        .bytes_as_str()
+        .deserialize(deserializer)
+        .unwrap_err();
+    let expected_message =
+        "invalid value: byte array, expected UTF-8 text, which is invalid starting at byte 3";
+    assert_eq!(error.to_string(), expected_message);
+
+    // A bytes closure, if there is one, takes precedence.
+    let deserializer = BytesDeserializer::<serde::de::value::Error>::new(b"al\xffce");
+    let v = UntaggedEnumVisitor::new()
+        .string(|name| Ok(Name::Owned(name.to_owned())))
+        .bytes(|bytes| Ok(Name::Bytes(bytes.to_vec())))
+        .bytes_as_str()
+        .deserialize(deserializer)
+        .unwrap();
+    assert_eq!(v, Name::Bytes(b"al\xffce".to_vec()));
+
+    let deserializer = BorrowedBytesDeserializer::<serde::de::value::Error>::new(b"alice");
+    let error = UntaggedEnumVisitor::new()
+        .borrowed_str(|name| Ok(Name::Borrowed(name)))
+        .deserialize(deserializer)
+        .unwrap_err();
+    let expected_message = "invalid type: byte array, expected a borrowed string";
+    assert_eq!(error.to_string(), expected_message);
+}
+
+#[test]
+fn test_scalar_as_seq() {
+    #[derive(PartialEq, Debug)]
+    enum Authors<'a> {
+        List(Vec<&'a str>),
+        Disabled(bool),
+    }
+
+    impl<'de> Deserialize<'de> for Authors<'de> {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .seq(|seq| seq.deserialize().map(Authors::List))
+                .bool(|disabled| Ok(Authors::Disabled(disabled)))
+                .scalar_as_seq()
+                .deserialize(deserializer)
+        }
+    }
+
+    #[derive(PartialEq, Debug)]
+    struct Ports(Vec<BTreeMap<String, u16>>);
+
+    fn deserialize_ports<'de, D>(deserializer: D) -> Result<Ports, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        UntaggedEnumVisitor::new()
+            .seq(|seq| seq.deserialize().map(Ports))
+            .scalar_as_seq()
+            .deserialize(deserializer)
+    }
+
+    #[derive(PartialEq, Debug)]
+    enum Levels {
+        One(u8),
+        Many(Vec<u16>),
+    }
+
+    fn deserialize_levels<'de, D>(deserializer: D) -> Result<Levels, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        UntaggedEnumVisitor::new()
+            .u8(|level| Ok(Levels::One(level)))
+            .seq(|seq| seq.deserialize().map(Levels::Many))
+            .scalar_as_seq()
+            .deserialize(deserializer)
+    }
+
+    let j = r#" "alice" "#;
+    let v: Authors = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Authors::List(vec!["alice"]));
+
+    let j = r#" ["alice", "bob"] "#;
+    let v: Authors = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Authors::List(vec!["alice", "bob"]));
+
+    let j = "false";
+    let v: Authors = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Authors::Disabled(false));
+
+    let j = "null";
+    let error = serde_json::from_str::<Authors>(j).unwrap_err();
+    let expected_message = "invalid type: null, expected a boolean or array at line 1 column 4";
+    assert_eq!(error.to_string(), expected_message);
+
+    let v = deserialize_ports(json!({"http": 80})).unwrap();
+    assert_eq!(v, Ports(vec![BTreeMap::from([("http".to_owned(), 80)])]));
+
+    let error = deserialize_ports(json!(443)).unwrap_err();
+    let expected_message = "invalid type: integer `443`, expected a map";
+    assert_eq!(error.to_string(), expected_message);
+
+    // Integers out of range of the integer closures are wrapped too.
+    let v = deserialize_levels(json!(3)).unwrap();
+    assert_eq!(v, Levels::One(3));
+
+    let v = deserialize_levels(json!(300)).unwrap();
+    assert_eq!(v, Levels::Many(vec![300]));
+
+    let error = deserialize_levels(json!(-1)).unwrap_err();
+    let expected_message = "invalid value: integer `-1`, expected u16";
+    assert_eq!(error.to_string(), expected_message);
+}
+
+#[test]
+fn test_unwrap_singleton_seq() {
+    #[derive(PartialEq, Debug)]
+    enum Param {
+        Int(u32),
+        Str(String),
+    }
+
+    impl<'de> Deserialize<'de> for Param {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .u32(|int| Ok(Param::Int(int)))
+                .string(|string| Ok(Param::Str(string.to_owned())))
+                .unwrap_singleton_seq()
+                .deserialize(deserializer)
+        }
+    }
+
+    let j = r#" ["page"] "#;
+    let v: Param = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Param::Str("page".to_owned()));
+
+    let j = "[2]";
+    let v: Param = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Param::Int(2));
+
+    let j = "2";
+    let v: Param = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Param::Int(2));
+
+    let j = "[]";
+    let error = serde_json::from_str::<Param>(j).unwrap_err();
+    let expected_message =
+        "invalid length 0, expected a sequence of one element at line 1 column 2";
+    assert_eq!(error.to_string(), expected_message);
+
+    let j = r#" [1, "two", [3]] "#;
+    let error = serde_json::from_str::<Param>(j).unwrap_err();
+    let expected_message =
+        "invalid length 3, expected a sequence of one element at line 1 column 16";
+    assert_eq!(error.to_string(), expected_message);
+
+    // Only one level of nesting is unwrapped.
+    let j = "[[1]]";
+    let error = serde_json::from_str::<Param>(j).unwrap_err();
+    let expected_message =
+        "invalid type: sequence, expected an integer or string at line 1 column 5";
+    assert_eq!(error.to_string(), expected_message);
+}
+
+#[test]
+fn test_pairs_as_map() {
+    #[derive(PartialEq, Debug)]
+    struct Headers(BTreeMap<String, u32>);
+
+    impl<'de> Deserialize<'de> for Headers {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .map(|map| map.deserialize().map(Headers))
+                .pairs_as_map()
+                .deserialize(deserializer)
+        }
+    }
+
+    let expected = Headers(BTreeMap::from([("a".to_owned(), 1), ("b".to_owned(), 2)]));
+
+    let j = r#" {"a": 1, "b": 2} "#;
+    let v: Headers = serde_json::from_str(j).unwrap();
+    assert_eq!(v, expected);
+
+    let j = r#" [["a", 1], ["b", 2]] "#;
+    let v: Headers = serde_json::from_str(j).unwrap();
+    assert_eq!(v, expected);
+
+    let j = r#" [{"key": "a", "value": 1}, {"value": 2, "key": "b"}] "#;
+    let v: Headers = serde_json::from_str(j).unwrap();
+    assert_eq!(v, expected);
+
+    let j = r#" [["a", 1, 2]] "#;
+    let error = serde_json::from_str::<Headers>(j).unwrap_err();
+    let expected_message = "invalid length 3, expected a [key, value] pair or a map with \"key\" and \"value\" entries at line 1 column 14";
+    assert_eq!(error.to_string(), expected_message);
+
+    let j = r#" ["a"] "#;
+    let error = serde_json::from_str::<Headers>(j).unwrap_err();
+    let expected_message = "invalid type: string \"a\", expected a [key, value] pair or a map with \"key\" and \"value\" entries at line 1 column 6";
+    assert_eq!(error.to_string(), expected_message);
+
+    let j = r#" [{"key": "a"}] "#;
+    let error = serde_json::from_str::<Headers>(j).unwrap_err();
+    let expected_message = "missing field `value` at line 1 column 15";
+    assert_eq!(error.to_string(), expected_message);
+
+    let j = r#" [{"key": "a", "val": 1}] "#;
+    let error = serde_json::from_str::<Headers>(j).unwrap_err();
+    let expected_message = "unknown field `val`, expected `key` or `value` at line 1 column 25";
+    assert_eq!(error.to_string(), expected_message);
+}
+
+#[test]
+fn test_index_map_as_seq() {
+    #[derive(PartialEq, Debug)]
+    struct Tags(Vec<String>);
+
+    impl<'de> Deserialize<'de> for Tags {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            UntaggedEnumVisitor::new()
+                .seq(|seq| seq.deserialize().map(Tags))
+                .index_map_as_seq()
+                .deserialize(deserializer)
+        }
+    }
+
+    let expected = Tags(vec!["x".to_owned(), "y".to_owned()]);
+
+    let j = r#" ["x", "y"] "#;
+    let v: Tags = serde_json::from_str(j).unwrap();
+    assert_eq!(v, expected);
+
+    let j = r#" {"0": "x", "1": "y"} "#;
+    let v: Tags = serde_json::from_str(j).unwrap();
+    assert_eq!(v, expected);
+
+    let j = "{}";
+    let v: Tags = serde_json::from_str(j).unwrap();
+    assert_eq!(v, Tags(Vec::new()));
+
+    let j = r#" {"0": "x", "2": "y"} "#;
+    let error = serde_json::from_str::<Tags>(j).unwrap_err();
+    let expected_message =
+        "invalid value: string \"2\", expected the index 1 as the next key at line 1 column 15";
+    assert_eq!(error.to_string(), expected_message);
+
+    let j = r#" {"00": "x"} "#;
+    let error = serde_json::from_str::<Tags>(j).unwrap_err();
+    let expected_message =
+        "invalid value: string \"00\", expected the index 0 as the next key at line 1 column 6";
+    assert_eq!(error.to_string(), expected_message);
+
+    // Formats with integer keys may produce them as signed integers.
+    let entries = vec![(0i64, "x"), (1i64, "y")];
+    let deserializer = MapDeserializer::<_, serde::de::value::Error>::new(entries.into_iter());
+    let v = Tags::deserialize(deserializer).unwrap();
+    assert_eq!(v, expected);
+
+    let entries = vec![(-1i32, "x")];
+    let deserializer = MapDeserializer::<_, serde::de::value::Error>::new(entries.into_iter());
+    let error = Tags::deserialize(deserializer).unwrap_err();
+    let expected_message = "invalid value: integer `-1`, expected the index 0 as the next key";
+    assert_eq!(error.to_string(), expected_message);
+}